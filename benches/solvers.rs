@@ -0,0 +1,112 @@
+use criterion::{criterion_group, criterion_main, BatchSize, BenchmarkId, Criterion, Throughput};
+use shut_the_box_rust::{
+    depth_solve, get_game_meta, get_roll_probs, get_state_cnt, get_tile_removal_possibilities,
+    naive_solve, par_solve, value_solve, Combiner, InitData, Tiles, Uns,
+};
+use std::collections::HashMap;
+
+/// Board sizes to solve, the classic board is 9 tiles
+const TILE_CNTS: [Uns; 4] = [5, 9, 12, 15];
+/// Numbers of six-sided dice to roll
+const DIE_CNTS: [Uns; 2] = [2, 3];
+/// Dice for the roll distribution, as (sides, number of dice)
+const ROLL_DICE: [(Uns, Uns); 5] = [(6, 2), (6, 3), (6, 4), (10, 3), (20, 4)];
+/// The naive solver doesn't memoize, so it only gets the boards it solves in reasonable time
+const NAIVE_MAX_TILES: Uns = 9;
+
+/// Returns the rules for a board of tiles 1 through tile_cnt rolled with die_cnt six-sided dice
+fn get_init_data(tile_cnt: Uns, die_cnt: Uns) -> InitData {
+    InitData {
+        die_cnt,
+        start_tiles: (1..=tile_cnt).collect(),
+        ..InitData::default()
+    }
+}
+
+/// Benchmarks every solver on every board size and dice config, with throughput counted in game states
+fn bench_solvers(c: &mut Criterion) {
+    let mut group = c.benchmark_group("solve");
+    group.sample_size(10);
+    for die_cnt in DIE_CNTS {
+        for tile_cnt in TILE_CNTS {
+            let init_data = get_init_data(tile_cnt, die_cnt);
+            let game_meta = get_game_meta(&init_data).unwrap();
+            let tiles: Tiles = init_data.start_tiles.clone();
+            let state_cnt = get_state_cnt(&game_meta);
+            let config = format!("{} tiles {}d6, {} states", tile_cnt, die_cnt, state_cnt);
+            group.throughput(Throughput::Elements(state_cnt));
+
+            if tile_cnt <= NAIVE_MAX_TILES && die_cnt == 2 {
+                group.bench_with_input(
+                    BenchmarkId::new("naive_solve", &config),
+                    &tiles,
+                    |b, tiles| b.iter(|| naive_solve(tiles.clone(), &game_meta)),
+                );
+            }
+            group.bench_with_input(
+                BenchmarkId::new("depth_solve", &config),
+                &tiles,
+                |b, tiles| b.iter(|| depth_solve(tiles.clone(), &game_meta, &mut HashMap::new())),
+            );
+            group.bench_with_input(
+                BenchmarkId::new("par_solve", &config),
+                &tiles,
+                |b, tiles| {
+                    b.iter_batched(
+                        || game_meta.clone(),
+                        |game_meta| par_solve(tiles.clone(), game_meta),
+                        BatchSize::LargeInput,
+                    )
+                },
+            );
+            group.bench_function(BenchmarkId::new("value_solve", &config), |b| {
+                b.iter(|| value_solve(&game_meta))
+            });
+        }
+    }
+    group.finish();
+}
+
+/// Benchmarks the setup every solve pays before solving
+fn bench_setup(c: &mut Criterion) {
+    let mut group = c.benchmark_group("setup");
+    for (sides, die_cnt) in ROLL_DICE {
+        let die_vals: Vec<Uns> = (1..=sides).collect();
+        group.throughput(Throughput::Elements(1));
+        for keep_dice in [false, true] {
+            let kept = if keep_dice { "dice" } else { "totals" };
+            group.bench_with_input(
+                BenchmarkId::new("get_roll_probs", format!("{}d{} {}", die_cnt, sides, kept)),
+                &die_vals,
+                |b, die_vals| {
+                    b.iter(|| get_roll_probs(die_vals, die_cnt, keep_dice, Combiner::Sum))
+                },
+            );
+        }
+    }
+    for die_cnt in DIE_CNTS {
+        let totals: Vec<Uns> = (1..=6 * die_cnt).collect();
+        for tile_cnt in TILE_CNTS {
+            let tiles: Tiles = (1..=tile_cnt).collect();
+            let config = format!("{} tiles {}d6", tile_cnt, die_cnt);
+            group.throughput(Throughput::Elements(totals.len() as u64));
+            group.bench_with_input(
+                BenchmarkId::new("get_tile_removal_possibilities", &config),
+                &tiles,
+                |b, tiles| b.iter(|| get_tile_removal_possibilities(tiles, &totals, &0)),
+            );
+
+            let init_data = get_init_data(tile_cnt, die_cnt);
+            group.throughput(Throughput::Elements(1));
+            group.bench_with_input(
+                BenchmarkId::new("get_game_meta", &config),
+                &init_data,
+                |b, init_data| b.iter(|| get_game_meta(init_data).unwrap()),
+            );
+        }
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_solvers, bench_setup);
+criterion_main!(benches);
@@ -0,0 +1,369 @@
+use crate::query::{get_state_policy, BestMove};
+use crate::{
+    get_dice_win_chances, get_reach_chances, get_roll_total, get_single_legality,
+    get_solved_best_states, get_tiles_string, Float, Roll, Tiles, Trunk, Uns, TIE_TOLERANCE,
+};
+use std::collections::HashMap;
+
+/// A single board situation on the cheat sheet
+struct CheatSheetRow {
+    /// The open tiles
+    tiles: Tiles,
+    /// Chance of this board coming up at some point in a perfectly played game
+    reach_chance: Float,
+    /// Perfect play win chance from this board
+    win_chance: Float,
+    /// Whether a single die should be rolled from this board
+    use_single: bool,
+    /// Whether perfect play stops here and keeps the score, when stopping early is allowed
+    stops: bool,
+    /// What to do after each roll, empty if it stops
+    best_moves: HashMap<Roll, BestMove>,
+}
+
+/// A column of the table, the rolls sharing a total that are played the same way on every row
+struct CheatSheetColumn {
+    /// The heading of the column
+    name: String,
+    /// What to do on each row, empty where none of the rolls is rolled
+    cells: Vec<String>,
+}
+
+/// Everything printed on a cheat sheet, shared by the Markdown and HTML outputs
+struct CheatSheet {
+    start_tiles: Tiles,
+    win_chance: Float,
+    single_rule: String,
+    columns: Vec<CheatSheetColumn>,
+    rows: Vec<CheatSheetRow>,
+}
+
+/// Writes a strategy cheat sheet for a solved game to the given path.
+/// Paths ending in .html get a standalone HTML page, everything else gets Markdown.
+pub fn write_cheat_sheet(trunk: &Trunk, path: &str, row_cnt: usize) -> std::io::Result<()> {
+    let cheat_sheet = get_cheat_sheet(trunk, row_cnt);
+    let out = if path.to_lowercase().ends_with(".html") {
+        get_cheat_sheet_html(&cheat_sheet)
+    } else {
+        get_cheat_sheet_markdown(&cheat_sheet)
+    };
+    std::fs::write(path, out)
+}
+
+/// Builds the cheat sheet from the most commonly reached boards of a solved game
+fn get_cheat_sheet(trunk: &Trunk, row_cnt: usize) -> CheatSheet {
+    let game_meta = &trunk.game_meta;
+    let reach_chances = get_reach_chances(trunk);
+
+    let rolls: Vec<Roll> = game_meta
+        .rolls
+        .iter()
+        .filter(|roll| roll.iter().sum::<Uns>() > 0)
+        .cloned()
+        .collect();
+
+    let mut reached: Vec<(&Tiles, Float)> = reach_chances
+        .iter()
+        .filter(|(tiles, reach_chance)| !tiles.is_empty() && **reach_chance > 0.)
+        .map(|(tiles, reach_chance)| (tiles, *reach_chance))
+        .collect();
+    reached.sort_by(|a, b| b.1.total_cmp(&a.1).then_with(|| a.0.cmp(b.0)));
+
+    let mut single_choices = Vec::new();
+    for (tiles, _) in &reached {
+        if get_single_legality(tiles, &game_meta.die_max) {
+            let best_states_hm = get_solved_best_states(tiles, game_meta, &trunk.game_db);
+            let (win_chance_single, win_chance_multi) =
+                get_dice_win_chances(tiles, &best_states_hm, game_meta);
+            let preference = if (win_chance_single - win_chance_multi).abs() <= TIE_TOLERANCE {
+                None
+            } else {
+                Some(win_chance_single > win_chance_multi)
+            };
+            single_choices.push(((*tiles).clone(), preference));
+        }
+    }
+
+    let rows: Vec<CheatSheetRow> = reached
+        .iter()
+        .take(row_cnt)
+        .map(|(tiles, reach_chance)| get_cheat_sheet_row(trunk, tiles, *reach_chance))
+        .collect();
+
+    CheatSheet {
+        start_tiles: game_meta.tiles.clone(),
+        win_chance: *trunk.game_db.get(&game_meta.tiles).unwrap(),
+        single_rule: get_single_rule(&single_choices),
+        columns: get_columns(trunk, &rolls, &rows),
+        rows,
+    }
+}
+
+/// Returns the columns of the table, one per roll, except that rolls with the same total share a column
+/// when no row plays them differently, so rolls keeping each die for a doubles rule don't need a column each.
+/// Rolls no row rolls get no column, ex: a single die when every row rolls them all.
+fn get_columns(trunk: &Trunk, rolls: &[Roll], rows: &[CheatSheetRow]) -> Vec<CheatSheetColumn> {
+    let game_meta = &trunk.game_meta;
+    let get_total = |roll: &Roll| get_roll_total(roll, game_meta.combiner, game_meta.doubles_rule);
+    // each group is (total, rolls, cells)
+    let mut groups: Vec<(Uns, Vec<&Roll>, Vec<String>)> = Vec::new();
+    for roll in rolls {
+        let total = get_total(roll);
+        let cells: Vec<String> = rows.iter().map(|row| get_move_string(row, roll)).collect();
+        if cells.iter().all(|cell| cell.is_empty()) {
+            continue;
+        }
+        let fits = |group_cells: &Vec<String>| {
+            group_cells
+                .iter()
+                .zip(&cells)
+                .all(|(a, b)| a.is_empty() || b.is_empty() || a == b)
+        };
+        match groups
+            .iter_mut()
+            .find(|(group_total, _, group_cells)| *group_total == total && fits(group_cells))
+        {
+            Some((_, group_rolls, group_cells)) => {
+                group_rolls.push(roll);
+                for (group_cell, cell) in group_cells.iter_mut().zip(cells) {
+                    if group_cell.is_empty() {
+                        *group_cell = cell;
+                    }
+                }
+            }
+            None => groups.push((total, vec![roll], cells)),
+        }
+    }
+
+    groups.sort_by_key(|(total, _, _)| *total);
+    // a shared column is named by its total, unless another shared column has the same total
+    let get_shared_cnt = |total: Uns| {
+        groups
+            .iter()
+            .filter(|(group_total, group_rolls, _)| *group_total == total && group_rolls.len() > 1)
+            .count()
+    };
+    let names: Vec<String> = groups
+        .iter()
+        .map(|(total, group_rolls, _)| {
+            if group_rolls.len() > 1 && get_shared_cnt(*total) == 1 {
+                total.to_string()
+            } else {
+                group_rolls
+                    .iter()
+                    .map(|roll| get_roll_string(roll))
+                    .collect::<Vec<String>>()
+                    .join(" ")
+            }
+        })
+        .collect();
+    groups
+        .into_iter()
+        .zip(names)
+        .map(|((_, _, cells), name)| CheatSheetColumn { name, cells })
+        .collect()
+}
+
+/// Returns the cheat sheet row for a given board, playing it the way the solved game's policy does
+fn get_cheat_sheet_row(trunk: &Trunk, tiles: &Tiles, reach_chance: Float) -> CheatSheetRow {
+    let state_policy = get_state_policy(tiles, trunk);
+    CheatSheetRow {
+        tiles: tiles.clone(),
+        reach_chance,
+        win_chance: state_policy.win_chance,
+        use_single: state_policy.use_single,
+        stops: state_policy.stops,
+        best_moves: state_policy
+            .choices
+            .into_iter()
+            .map(|choice| (choice.roll, choice.best_move))
+            .collect(),
+    }
+}
+
+/// Summarizes when to roll a single die as a threshold on the sum of the open tiles.
+/// Each choice is (tiles, Some(true) if single is better, Some(false) if multi is better, None if equal)
+fn get_single_rule(single_choices: &[(Tiles, Option<bool>)]) -> String {
+    let decided: Vec<(Uns, &Tiles, bool)> = single_choices
+        .iter()
+        .filter_map(|(tiles, preference)| preference.map(|p| (tiles.iter().sum(), tiles, p)))
+        .collect();
+    if decided.is_empty() {
+        return "It never matters whether you roll one die or all of them.".to_string();
+    }
+    if decided.iter().all(|(_, _, single)| *single) {
+        return "Roll one die whenever it's allowed.".to_string();
+    }
+    if decided.iter().all(|(_, _, single)| !*single) {
+        return "Never roll one die.".to_string();
+    }
+
+    // pick the threshold with the fewest boards that break the rule "one die iff sum <= threshold"
+    let max_sum = decided.iter().map(|(sum, _, _)| *sum).max().unwrap();
+    let mut best_threshold = 0;
+    let mut best_exceptions = usize::MAX;
+    for threshold in 0..=max_sum {
+        let exceptions = decided
+            .iter()
+            .filter(|(sum, _, single)| (*sum <= threshold) != *single)
+            .count();
+        if exceptions < best_exceptions {
+            best_threshold = threshold;
+            best_exceptions = exceptions;
+        }
+    }
+
+    let mut rule = format!(
+        "Roll one die whenever it's allowed and the open tiles add up to {} or less.",
+        best_threshold
+    );
+    let mut exceptions: Vec<&(Uns, &Tiles, bool)> = decided
+        .iter()
+        .filter(|(sum, _, single)| (*sum <= best_threshold) != *single)
+        .collect();
+    exceptions.sort_by(|a, b| a.1.cmp(b.1));
+    for (_, tiles, single) in exceptions {
+        let dice = if *single { "one die" } else { "all dice" };
+        rule.push_str(&format!(
+            "\nException: with {} open, roll {}.",
+            get_tiles_string(tiles),
+            dice
+        ));
+    }
+    rule
+}
+
+/// Returns a roll as a readable String, ex: "8" for a total or "3,5" for the faces of each die
+fn get_roll_string(roll: &Roll) -> String {
+    roll.iter()
+        .map(|die| die.to_string())
+        .collect::<Vec<String>>()
+        .join(",")
+}
+
+/// Returns the text of a single table cell for a given roll on a given row, empty where the roll isn't rolled
+fn get_move_string(row: &CheatSheetRow, roll: &Roll) -> String {
+    let get_tiles_sum = |tiles: &Tiles| {
+        tiles
+            .iter()
+            .map(|tile| tile.to_string())
+            .collect::<Vec<String>>()
+            .join("+")
+    };
+    match row.best_moves.get(roll) {
+        Some(BestMove::Flip(flip)) => get_tiles_sum(flip),
+        Some(BestMove::Reopen(reopen)) => format!("open {}", get_tiles_sum(reopen)),
+        Some(BestMove::RollAgain) => "again".to_string(),
+        Some(BestMove::SpendMulligan) => "mulligan".to_string(),
+        Some(BestMove::Stuck) => "stuck".to_string(),
+        None => String::new(),
+    }
+}
+
+/// Returns the text of the dice cell of a row: which dice to roll, or stop
+fn get_dice_string(row: &CheatSheetRow) -> &'static str {
+    if row.stops {
+        "stop"
+    } else if row.use_single {
+        "one"
+    } else {
+        "all"
+    }
+}
+
+/// Returns the cheat sheet as Markdown
+fn get_cheat_sheet_markdown(cheat_sheet: &CheatSheet) -> String {
+    let mut out = String::new();
+    out.push_str("# Shut the Box Cheat Sheet\n\n");
+    out.push_str(&format!(
+        "Starting tiles: {}. Perfect play wins {:.2}% of games.\n\n",
+        get_tiles_string(&cheat_sheet.start_tiles),
+        cheat_sheet.win_chance * 100.
+    ));
+
+    out.push_str("## When to Roll One Die\n\n");
+    for line in cheat_sheet.single_rule.lines() {
+        out.push_str(&format!("- {}\n", line));
+    }
+
+    out.push_str("\n## Which Tiles to Flip\n\n");
+    out.push_str("Most common boards first. Find your open tiles, then your roll.\n\n");
+    out.push_str("| Open tiles | Seen | Win | Dice |");
+    for column in &cheat_sheet.columns {
+        out.push_str(&format!(" {} |", column.name));
+    }
+    out.push_str("\n|---|---|---|---|");
+    for _ in &cheat_sheet.columns {
+        out.push_str("---|");
+    }
+    out.push('\n');
+    for (row_id, row) in cheat_sheet.rows.iter().enumerate() {
+        out.push_str(&format!(
+            "| {} | {:.1}% | {:.1}% | {} |",
+            get_tiles_string(&row.tiles),
+            row.reach_chance * 100.,
+            row.win_chance * 100.,
+            get_dice_string(row)
+        ));
+        for column in &cheat_sheet.columns {
+            out.push_str(&format!(" {} |", column.cells[row_id]));
+        }
+        out.push('\n');
+    }
+    out
+}
+
+/// Returns the cheat sheet as a standalone HTML page
+fn get_cheat_sheet_html(cheat_sheet: &CheatSheet) -> String {
+    let mut out = String::new();
+    out.push_str("<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n");
+    out.push_str("<title>Shut the Box Cheat Sheet</title>\n<style>\n");
+    out.push_str("body { font-family: sans-serif; margin: 2em; }\n");
+    out.push_str("table { border-collapse: collapse; }\n");
+    out.push_str("th, td { border: 1px solid #999; padding: 0.2em 0.5em; text-align: center; }\n");
+    out.push_str("th { background: #eee; }\n");
+    out.push_str("td.stuck { color: #b00; }\n");
+    out.push_str("@media print { body { margin: 0; font-size: 10pt; } }\n");
+    out.push_str("</style>\n</head>\n<body>\n");
+
+    out.push_str("<h1>Shut the Box Cheat Sheet</h1>\n");
+    out.push_str(&format!(
+        "<p>Starting tiles: {}. Perfect play wins {:.2}% of games.</p>\n",
+        get_tiles_string(&cheat_sheet.start_tiles),
+        cheat_sheet.win_chance * 100.
+    ));
+
+    out.push_str("<h2>When to Roll One Die</h2>\n<ul>\n");
+    for line in cheat_sheet.single_rule.lines() {
+        out.push_str(&format!("<li>{}</li>\n", line));
+    }
+    out.push_str("</ul>\n");
+
+    out.push_str("<h2>Which Tiles to Flip</h2>\n");
+    out.push_str("<p>Most common boards first. Find your open tiles, then your roll.</p>\n");
+    out.push_str("<table>\n<tr><th>Open tiles</th><th>Seen</th><th>Win</th><th>Dice</th>");
+    for column in &cheat_sheet.columns {
+        out.push_str(&format!("<th>{}</th>", column.name));
+    }
+    out.push_str("</tr>\n");
+    for (row_id, row) in cheat_sheet.rows.iter().enumerate() {
+        out.push_str(&format!(
+            "<tr><th>{}</th><td>{:.1}%</td><td>{:.1}%</td><td>{}</td>",
+            get_tiles_string(&row.tiles),
+            row.reach_chance * 100.,
+            row.win_chance * 100.,
+            get_dice_string(row)
+        ));
+        for column in &cheat_sheet.columns {
+            let flip = &column.cells[row_id];
+            if flip == "stuck" {
+                out.push_str(&format!("<td class=\"stuck\">{}</td>", flip));
+            } else {
+                out.push_str(&format!("<td>{}</td>", flip));
+            }
+        }
+        out.push_str("</tr>\n");
+    }
+    out.push_str("</table>\n</body>\n</html>\n");
+    out
+}
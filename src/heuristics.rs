@@ -0,0 +1,214 @@
+use crate::{
+    get_flipped_tiles, policy_solve, Float, GameMeta, Policy, Tiles, Trunk, Uns, TIE_TOLERANCE,
+};
+use std::collections::HashMap;
+
+/// A way to rank the legal moves for a roll, applied in order until a single move is left
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum MoveKey {
+    /// Flip as few tiles as possible
+    Fewest,
+    /// Flip as many tiles as possible
+    Most,
+    /// Flip the highest tiles possible, comparing the highest flipped tile first
+    Highest,
+    /// Flip the lowest tiles possible, comparing the highest flipped tile first
+    Lowest,
+}
+
+/// When to roll a single die, only considered when it's legal
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum SingleRule {
+    Never,
+    Always,
+    /// Roll one die when the open tiles add up to at most this much
+    SumAtMost(Uns),
+}
+
+/// A simple rule a person could memorize
+#[derive(Debug, Clone)]
+struct Heuristic {
+    /// Move rankings applied in order, the last one always picks a single move
+    move_keys: Vec<MoveKey>,
+    single_rule: SingleRule,
+}
+
+impl Policy for Heuristic {
+    fn get_use_single(&self, tiles: &Tiles) -> bool {
+        match self.single_rule {
+            SingleRule::Never => false,
+            SingleRule::Always => true,
+            SingleRule::SumAtMost(max_sum) => tiles.iter().sum::<Uns>() <= max_sum,
+        }
+    }
+
+    fn get_next_state(&self, tiles: &Tiles, next_states: &[Tiles]) -> Tiles {
+        next_states
+            .iter()
+            .min_by(|a, b| {
+                let a_flipped = get_flipped_tiles(tiles, a);
+                let b_flipped = get_flipped_tiles(tiles, b);
+                let mut ordering = std::cmp::Ordering::Equal;
+                for move_key in &self.move_keys {
+                    ordering = ordering.then_with(|| match move_key {
+                        MoveKey::Fewest => a_flipped.len().cmp(&b_flipped.len()),
+                        MoveKey::Most => b_flipped.len().cmp(&a_flipped.len()),
+                        MoveKey::Highest => b_flipped.cmp(&a_flipped),
+                        MoveKey::Lowest => a_flipped.cmp(&b_flipped),
+                    });
+                }
+                ordering
+            })
+            .unwrap()
+            .clone()
+    }
+}
+
+impl Heuristic {
+    /// Returns a readable description of the heuristic, ex: "Flip the fewest tiles, then the highest; ..."
+    fn get_description(&self) -> String {
+        let moves = self
+            .move_keys
+            .iter()
+            .map(|move_key| match move_key {
+                MoveKey::Fewest => "the fewest tiles",
+                MoveKey::Most => "the most tiles",
+                MoveKey::Highest => "the highest tiles",
+                MoveKey::Lowest => "the lowest tiles",
+            })
+            .collect::<Vec<&str>>()
+            .join(", tie-break ");
+        let single = match self.single_rule {
+            SingleRule::Never => "never roll one die".to_string(),
+            SingleRule::Always => "roll one die whenever allowed".to_string(),
+            SingleRule::SumAtMost(max_sum) => {
+                format!(
+                    "roll one die when allowed and the tiles add up to {} or less",
+                    max_sum
+                )
+            }
+        };
+        format!("Flip {}; {}", moves, single)
+    }
+
+    /// Returns how many separate rules need to be remembered
+    fn get_size(&self) -> usize {
+        let single_size = match self.single_rule {
+            SingleRule::Never | SingleRule::Always => 0,
+            SingleRule::SumAtMost(_) => 1,
+        };
+        self.move_keys.len() + single_size
+    }
+}
+
+/// A heuristic and how well it plays
+struct HeuristicResult {
+    heuristic: Heuristic,
+    win_chance: Float,
+}
+
+/// Returns every heuristic in the searched family for a given game
+fn get_heuristics(game_meta: &GameMeta) -> Vec<Heuristic> {
+    let move_key_lists = vec![
+        vec![MoveKey::Highest],
+        vec![MoveKey::Lowest],
+        vec![MoveKey::Fewest, MoveKey::Highest],
+        vec![MoveKey::Fewest, MoveKey::Lowest],
+        vec![MoveKey::Most, MoveKey::Highest],
+        vec![MoveKey::Most, MoveKey::Lowest],
+    ];
+
+    let single_tiles: Vec<Uns> = game_meta
+        .tiles
+        .iter()
+        .copied()
+        .filter(|tile| *tile <= game_meta.die_max)
+        .collect();
+    let mut single_rules = vec![SingleRule::Never, SingleRule::Always];
+    if !single_tiles.is_empty() {
+        let max_sum: Uns = single_tiles.iter().sum();
+        for sum in 1..max_sum {
+            single_rules.push(SingleRule::SumAtMost(sum));
+        }
+    }
+
+    let mut heuristics = Vec::new();
+    for move_keys in &move_key_lists {
+        for single_rule in &single_rules {
+            heuristics.push(Heuristic {
+                move_keys: move_keys.clone(),
+                single_rule: *single_rule,
+            });
+        }
+    }
+    heuristics
+}
+
+/// Evaluates every heuristic against a solved game, best first
+fn get_heuristic_results(trunk: &Trunk) -> Vec<HeuristicResult> {
+    let game_meta = &trunk.game_meta;
+    let mut results: Vec<HeuristicResult> = get_heuristics(game_meta)
+        .into_iter()
+        .map(|heuristic| {
+            let mut game_db = HashMap::new();
+            let win_chance =
+                policy_solve(game_meta.tiles.clone(), game_meta, &heuristic, &mut game_db);
+            HeuristicResult {
+                heuristic,
+                win_chance,
+            }
+        })
+        .collect();
+    results.sort_by(|a, b| {
+        b.win_chance
+            .total_cmp(&a.win_chance)
+            .then_with(|| a.heuristic.get_size().cmp(&b.heuristic.get_size()))
+    });
+    results
+}
+
+/// Returns a readable report of how much win chance the best simple heuristics lose versus perfect play.
+/// Heuristics play without the rules' mulligans, so part of the loss with mulligans is the unused mulligans.
+pub fn get_readable_heuristics_string(trunk: &Trunk, row_cnt: usize) -> String {
+    let optimal = *trunk.game_db.get(&trunk.game_meta.tiles).unwrap();
+    let results = get_heuristic_results(trunk);
+
+    let mut s = String::new();
+    s.push_str(&format!(
+        "Perfect play win chance: {:.4}%\n",
+        optimal * 100.
+    ));
+    if trunk.game_meta.mulligans > 0 {
+        s.push_str("Heuristics never spend a mulligan, perfect play does\n");
+    }
+    s.push_str(&format!(
+        "Best {} of {} heuristics (loss is in percentage points):\n",
+        row_cnt.min(results.len()),
+        results.len()
+    ));
+    for result in results.iter().take(row_cnt) {
+        s.push_str(&format!(
+            "  {:.4}% (loss {:.4}) {}\n",
+            result.win_chance * 100.,
+            (optimal - result.win_chance) * 100.,
+            result.heuristic.get_description()
+        ));
+    }
+    let best_win_chance = results.first().map_or(0., |result| result.win_chance);
+    let recommended = results
+        .iter()
+        .filter(|result| result.win_chance >= best_win_chance - TIE_TOLERANCE)
+        .min_by_key(|result| result.heuristic.get_size());
+    if let Some(best) = recommended {
+        s.push_str(&format!(
+            "Recommended: {} ({:.2}% of perfect play)\n",
+            best.heuristic.get_description(),
+            if optimal > 0. {
+                best.win_chance / optimal * 100.
+            } else {
+                100.
+            }
+        ));
+    }
+    s
+}
@@ -0,0 +1,350 @@
+use crate::query::{query_roll, query_state, BestMove, RollChoice, StateQuery};
+use crate::{
+    get_scores_fit, get_srt, get_totals_fit, get_trunk, try_get_solvable_game_meta, BoxMode,
+    Combiner, DoublesRule, InitData, RemovalLimits, RemovalRule, Tiles, Trunk, Uns, MAX_SCORE,
+};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::collections::{HashMap, VecDeque};
+
+/// The most tiles a board may have, since every one of them could be flippable
+const MAX_TILES: usize = 64;
+/// The most dice a request may roll, to keep setting up the rolls quick
+const MAX_DICE: Uns = 10;
+/// The most faces a die may have, for the same reason
+const MAX_FACES: usize = 100;
+/// The most mulligans a request may give, each one solves the whole game again
+const MAX_MULLIGANS: Uns = 10;
+
+/// Game rules of a request, named like the rules file settings. Rules left out are the classic rules.
+#[derive(Deserialize, Default)]
+#[serde(default, deny_unknown_fields)]
+struct RulesRequest {
+    d_min: Option<Uns>,
+    d_max: Option<Uns>,
+    d_direct: Vec<Uns>,
+    die_cnt: Option<Uns>,
+    t_min: Option<Uns>,
+    t_max: Option<Uns>,
+    t_direct: Vec<Uns>,
+    points: HashMap<Uns, Uns>,
+    max_remove: Uns,
+    exact_count: Uns,
+    adjacent: bool,
+    include_highest: bool,
+    one_above: Option<Uns>,
+    combine: Option<String>,
+    removal: Option<String>,
+    doubles: Option<String>,
+    stop_early: bool,
+    mulligans: Uns,
+    mode: Option<String>,
+}
+
+/// The body of every request, the rules of the game and the game state and roll to look up where needed
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Request {
+    #[serde(default)]
+    rules: RulesRequest,
+    /// The open tiles to look up, the starting tiles if left out
+    tiles: Option<Tiles>,
+    /// The face of each die rolled
+    roll: Option<Vec<Uns>>,
+}
+
+/// Everything about the rules that changes the solved game, solved games are cached by these
+#[derive(Clone, PartialEq, Eq, Hash)]
+struct RulesKey {
+    die_vals: Vec<Uns>,
+    die_cnt: Uns,
+    start_tiles: Tiles,
+    /// Sorted by tile
+    tile_points: Vec<(Uns, Uns)>,
+    max_remove: Uns,
+    box_mode: BoxMode,
+    combiner: Combiner,
+    removal_rule: RemovalRule,
+    removal_limits: RemovalLimits,
+    doubles_rule: DoublesRule,
+    stop_early: bool,
+    mulligans: Uns,
+}
+
+impl RulesKey {
+    fn new(init_data: &InitData) -> RulesKey {
+        let mut tile_points: Vec<(Uns, Uns)> = init_data
+            .tile_points
+            .iter()
+            .map(|(tile, points)| (*tile, *points))
+            .collect();
+        tile_points.sort_unstable();
+        RulesKey {
+            die_vals: get_srt(&init_data.die_vals),
+            die_cnt: init_data.die_cnt,
+            start_tiles: get_srt(&init_data.start_tiles),
+            tile_points,
+            max_remove: init_data.max_remove,
+            box_mode: init_data.box_mode,
+            combiner: init_data.combiner,
+            removal_rule: init_data.removal_rule,
+            removal_limits: init_data.removal_limits.clone(),
+            doubles_rule: init_data.doubles_rule,
+            stop_early: init_data.stop_early,
+            mulligans: init_data.mulligans,
+        }
+    }
+}
+
+/// Answers JSON requests about any rules, keeping recently solved games in memory
+/// so repeated requests for the same rules are instant
+pub struct JsonApi {
+    /// The most game states to solve for a single request
+    max_states: u64,
+    trunks: TrunkCache,
+}
+
+impl JsonApi {
+    /// Answers requests solving at most max_states game states each, and keeping at most that many between requests
+    pub fn new(max_states: u64) -> JsonApi {
+        JsonApi {
+            max_states,
+            trunks: TrunkCache {
+                max_states,
+                trunks: HashMap::new(),
+                keys: VecDeque::new(),
+            },
+        }
+    }
+
+    /// Returns the HTTP status code and JSON body answering a request to a path: /solve, /query, or /move.
+    /// Bodies of failed requests hold why in an error field.
+    pub fn answer(&mut self, path: &str, body: &str) -> (u16, String) {
+        let (status, body) = get_response(path, body, self.max_states, &mut self.trunks);
+        (status, body.to_string())
+    }
+}
+
+/// Solved games kept between requests, forgetting the ones solved longest ago once they hold too many game states
+struct TrunkCache {
+    /// The most game states to keep across every cached game
+    max_states: u64,
+    trunks: HashMap<RulesKey, Trunk>,
+    /// The rules of every cached game, solved longest ago first
+    keys: VecDeque<RulesKey>,
+}
+
+impl TrunkCache {
+    /// Returns the solved game of the rules and whether it was cached, solving it if it wasn't,
+    /// or why the rules can't be solved
+    fn get_trunk(&mut self, init_data: &InitData) -> Result<(&mut Trunk, bool), String> {
+        let key = RulesKey::new(init_data);
+        let cached = self.trunks.contains_key(&key);
+        if !cached {
+            let trunk = get_trunk(try_get_solvable_game_meta(init_data)?);
+            let mut state_cnt = get_trunk_state_cnt(&trunk);
+            for trunk in self.trunks.values() {
+                state_cnt = state_cnt.saturating_add(get_trunk_state_cnt(trunk));
+            }
+            while state_cnt > self.max_states {
+                let Some(old_key) = self.keys.pop_front() else {
+                    break;
+                };
+                if let Some(old_trunk) = self.trunks.remove(&old_key) {
+                    state_cnt -= get_trunk_state_cnt(&old_trunk);
+                }
+            }
+            self.keys.push_back(key.clone());
+            self.trunks.insert(key.clone(), trunk);
+        }
+        Ok((self.trunks.get_mut(&key).unwrap(), cached))
+    }
+}
+
+/// Returns the game states a solved game keeps in memory, counting the games solved for fewer mulligans left
+fn get_trunk_state_cnt(trunk: &Trunk) -> u64 {
+    let state_cnt = trunk.game_db.len() as u64;
+    state_cnt.saturating_mul(trunk.game_meta.mulligans as u64 + 1)
+}
+
+/// Returns the status code and JSON body answering a request to a path
+fn get_response(path: &str, text: &str, max_states: u64, trunks: &mut TrunkCache) -> (u16, Value) {
+    let answer = match path {
+        "/solve" | "/query" | "/move" => get_answer(path, text, max_states, trunks),
+        _ => {
+            return (
+                404,
+                get_error_json(&format!(
+                    "unknown path {}, expected /solve, /query, or /move",
+                    path
+                )),
+            )
+        }
+    };
+    match answer {
+        Ok(body) => (200, body),
+        Err(err) => (400, get_error_json(&err)),
+    }
+}
+
+/// Returns the JSON answer to a request to a known path, or why it can't be answered
+fn get_answer(
+    path: &str,
+    text: &str,
+    max_states: u64,
+    trunks: &mut TrunkCache,
+) -> Result<Value, String> {
+    let request: Request = serde_json::from_str(text).map_err(|err| err.to_string())?;
+    let init_data = get_init_data(&request.rules, max_states)?;
+    let (trunk, cached) = trunks.get_trunk(&init_data)?;
+    let tiles = request.tiles.unwrap_or(init_data.start_tiles);
+    match path {
+        "/solve" => {
+            let state_query = query_state(trunk, &tiles)?;
+            Ok(json!({
+                "tiles": state_query.tiles,
+                "win_chance": state_query.win_chance,
+                "expected_score": state_query.expected_score,
+                "states": trunk.game_db.len(),
+                "cached": cached,
+            }))
+        }
+        "/query" => Ok(get_state_query_json(&query_state(trunk, &tiles)?)),
+        _ => {
+            let dice = request.roll.ok_or("/move needs the dice rolled in roll")?;
+            let roll_choice = query_roll(trunk, &tiles, &dice)?;
+            let mut body = get_roll_choice_json(&roll_choice);
+            body["tiles"] = json!(get_srt(&tiles));
+            body["dice"] = json!(dice);
+            Ok(body)
+        }
+    }
+}
+
+/// Returns the init data for rules written as a JSON object, or why they can't be solved here
+#[cfg(feature = "python")]
+pub(crate) fn get_rules_init_data(text: &str, max_states: u64) -> Result<InitData, String> {
+    let rules: RulesRequest = serde_json::from_str(text).map_err(|err| err.to_string())?;
+    get_init_data(&rules, max_states)
+}
+
+/// Returns the init data for the rules of a request, or why they can't be solved here
+fn get_init_data(rules: &RulesRequest, max_states: u64) -> Result<InitData, String> {
+    let (d_min, d_max) = (rules.d_min.unwrap_or(1), rules.d_max.unwrap_or(6));
+    let (t_min, t_max) = (rules.t_min.unwrap_or(1), rules.t_max.unwrap_or(9));
+    // ranges are measured before they are filled in, so a huge one can't take all the memory
+    let face_cnt = get_range_len(d_min, d_max, &rules.d_direct);
+    let tile_cnt = get_range_len(t_min, t_max, &rules.t_direct);
+    if face_cnt == 0 {
+        return Err("the dice have no faces".to_string());
+    }
+    if face_cnt > MAX_FACES {
+        return Err(format!("dice may have at most {} faces", MAX_FACES));
+    }
+    if tile_cnt > MAX_TILES {
+        return Err(format!("boards may have at most {} tiles", MAX_TILES));
+    }
+    if rules.max_remove as usize > MAX_TILES || rules.exact_count as usize > MAX_TILES {
+        return Err(format!(
+            "turns may remove at most {} tiles, the most a board has",
+            MAX_TILES
+        ));
+    }
+    if rules.mulligans > MAX_MULLIGANS {
+        return Err(format!("give at most {} mulligans", MAX_MULLIGANS));
+    }
+    let die_vals: Vec<Uns> = if rules.d_direct.is_empty() {
+        (d_min..=d_max).collect()
+    } else {
+        rules.d_direct.clone()
+    };
+    let start_tiles: Tiles = if rules.t_direct.is_empty() {
+        (t_min..=t_max).collect()
+    } else {
+        rules.t_direct.clone()
+    };
+    let die_cnt = rules.die_cnt.unwrap_or(2);
+    if !(1..=MAX_DICE).contains(&die_cnt) {
+        return Err(format!("roll 1 to {} dice", MAX_DICE));
+    }
+    if !get_totals_fit(&die_vals, die_cnt) {
+        return Err(format!("rolls can total more than {}", Uns::MAX));
+    }
+    if !get_scores_fit(&start_tiles, &rules.points) {
+        return Err(format!(
+            "the board's points add up to more than {}",
+            MAX_SCORE
+        ));
+    }
+    Ok(InitData {
+        die_vals,
+        die_cnt,
+        start_tiles,
+        tile_points: rules.points.clone(),
+        max_remove: rules.max_remove,
+        box_mode: rules.mode.as_deref().unwrap_or("shut").parse()?,
+        combiner: rules.combine.as_deref().unwrap_or("sum").parse()?,
+        removal_rule: rules.removal.as_deref().unwrap_or("sum").parse()?,
+        removal_limits: RemovalLimits {
+            exact_count: rules.exact_count,
+            adjacent: rules.adjacent,
+            include_highest: rules.include_highest,
+            one_above: rules.one_above,
+        },
+        doubles_rule: rules.doubles.as_deref().unwrap_or("none").parse()?,
+        stop_early: rules.stop_early,
+        mulligans: rules.mulligans,
+        max_states,
+        ..InitData::default()
+    })
+}
+
+/// Returns how many values a setting lists, the given values or else the range from low to high
+fn get_range_len(low: Uns, high: Uns, direct: &[Uns]) -> usize {
+    if !direct.is_empty() {
+        direct.len()
+    } else if low > high {
+        0
+    } else {
+        ((high - low) as usize).saturating_add(1)
+    }
+}
+
+/// Returns a game state query as JSON
+pub(crate) fn get_state_query_json(state_query: &StateQuery) -> Value {
+    json!({
+        "tiles": state_query.tiles,
+        "win_chance": state_query.win_chance,
+        "expected_score": state_query.expected_score,
+        "single_legal": state_query.single_legal,
+        "use_single": state_query.use_single,
+        "stops": state_query.stops,
+        "choices": state_query
+            .choices
+            .iter()
+            .map(get_roll_choice_json)
+            .collect::<Vec<Value>>(),
+    })
+}
+
+/// Returns the best move for a roll as JSON, ex: {"roll": [8], "move": "flip", "flip": [7, 1], "win_chance": 0.17}
+pub(crate) fn get_roll_choice_json(roll_choice: &RollChoice) -> Value {
+    let (best_move, flip) = match &roll_choice.best_move {
+        BestMove::Flip(flip) => ("flip", Some(flip)),
+        BestMove::Reopen(flip) => ("open", Some(flip)),
+        BestMove::RollAgain => ("roll_again", None),
+        BestMove::SpendMulligan => ("spend_mulligan", None),
+        BestMove::Stuck => ("stuck", None),
+    };
+    json!({
+        "roll": roll_choice.roll,
+        "move": best_move,
+        "flip": flip,
+        "win_chance": roll_choice.win_chance,
+    })
+}
+
+/// Returns the body of an error response
+pub(crate) fn get_error_json(err: &str) -> Value {
+    json!({ "error": err })
+}
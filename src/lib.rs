@@ -0,0 +1,1750 @@
+pub mod cheat_sheet;
+pub mod heuristics;
+#[cfg(feature = "json")]
+pub mod json_api;
+pub mod match_play;
+pub mod move_gen;
+#[cfg(feature = "python")]
+mod python;
+pub mod query;
+pub mod rules;
+#[cfg(feature = "serve")]
+pub mod serve;
+pub mod simulate;
+pub mod stopping;
+pub mod utility;
+#[cfg(feature = "wasm")]
+mod wasm;
+
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+use std::collections::HashMap;
+#[cfg(feature = "parallel")]
+use std::collections::HashSet;
+use std::str::FromStr;
+use std::sync::Arc;
+
+/// The unsigned int type used for all non-usize int calculations
+pub type Uns = u32;
+/// The float type used for all float calculations
+pub type Float = f64;
+/// Helper type alias for a vector of tile values, for readability
+pub type Tiles = Vec<Uns>;
+/// Helper type alias for a roll of the dice: the sorted faces of each die, or just the total when only totals matter
+pub type Roll = Vec<Uns>;
+
+/// Value iteration stops once no win chance changes by more than this in a sweep
+const VALUE_ITERATION_TOLERANCE: Float = 1e-12;
+/// Value iteration gives up after this many sweeps over every game state
+const VALUE_ITERATION_MAX_SWEEPS: usize = 100_000;
+/// The most the points of a board may add up to, since the reports keep a chance or utility for every score
+const MAX_SCORE: Uns = 100_000;
+/// Win chances closer than this are treated as equal when comparing choices in the reports
+pub(crate) const TIE_TOLERANCE: Float = 1e-12;
+
+/// The parent of a given game containing all data from solving the game
+#[derive(Debug)]
+pub struct Trunk {
+    pub game_meta: GameMeta,
+
+    pub game_db: HashMap<Tiles, Float>, // contains all possible child game states
+}
+
+// TODO allow multiple algos?
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Algorithm {
+    All,
+    Naive,
+    Depth,
+    Parallel,
+    Value,
+    Default,
+}
+
+/// Which way tiles are flipped over the course of a game
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum BoxMode {
+    /// Start with every tile open and shut tiles summing to each roll
+    Shut,
+    /// Start with every tile open and shut tiles summing to each roll,
+    /// but a roll that can't shut any tiles re-opens a single shut tile matching it, whatever the removal limits.
+    /// The game is lost when a roll can do neither.
+    OpenAndShut,
+}
+
+/// Which tiles a roll allows to be flipped
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum RemovalRule {
+    /// Flip tiles summing to the dice total
+    SumOnly,
+    /// Flip the tile matching each die, doubles flip the one tile
+    IndividualOnly,
+    /// Flip either tiles summing to the dice total or the tile matching each die
+    SumOrIndividual,
+}
+
+/// Limits on which tiles may be flipped together, on top of the removal rule
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct RemovalLimits {
+    /// Flip exactly this many tiles, 0 for any number
+    pub exact_count: Uns,
+    /// Flipped tiles must be next to each other among the starting tiles
+    pub adjacent: bool,
+    /// Every flip must include the highest tile it could flip
+    pub include_highest: bool,
+    /// At most one flipped tile may be above this, None for no limit
+    pub one_above: Option<Uns>,
+}
+
+/// How the dice of a roll combine into the total that flipped tiles must add up to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Combiner {
+    Sum,
+    Product,
+    /// The highest die minus the lowest, a single die counts as its face
+    Difference,
+    /// The highest die
+    Max,
+}
+
+impl Combiner {
+    /// Returns the total the given dice combine into
+    fn get_total(&self, dice: &[Uns]) -> Uns {
+        let highest = dice.iter().copied().max().unwrap_or(0);
+        match self {
+            Combiner::Sum => dice
+                .iter()
+                .fold(0, |total: Uns, die| total.saturating_add(*die)),
+            Combiner::Product => dice
+                .iter()
+                .fold(1, |total: Uns, die| total.saturating_mul(*die)),
+            Combiner::Difference if dice.len() > 1 => highest - dice.iter().min().unwrap(),
+            Combiner::Difference | Combiner::Max => highest,
+        }
+    }
+
+    /// Returns just enough of the dice rolled so far to get their total, after rolling another die
+    fn get_partial(&self, partial: &[Uns], die: Uns) -> Vec<Uns> {
+        let (lowest, highest) = match partial {
+            [] => return vec![die],
+            [total] => (*total, *total),
+            _ => (partial[0], partial[partial.len() - 1]),
+        };
+        match self {
+            Combiner::Sum => vec![highest.saturating_add(die)],
+            Combiner::Product => vec![highest.saturating_mul(die)],
+            Combiner::Difference => vec![lowest.min(die), highest.max(die)],
+            Combiner::Max => vec![highest.max(die)],
+        }
+    }
+}
+
+/// What rolling doubles does, on top of the normal rules
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DoublesRule {
+    NoBonus,
+    /// Doubles may be rerolled instead of flipping tiles
+    Reroll,
+    /// Doubles are rolled again instead of losing when no tiles can be flipped
+    SkipIfStuck,
+    /// Doubles count as twice their total
+    DoubleCount,
+}
+
+impl DoublesRule {
+    /// Returns whether doubles can lead to rolling again from the same game state
+    pub fn get_rolls_again(&self) -> bool {
+        *self == DoublesRule::Reroll || *self == DoublesRule::SkipIfStuck
+    }
+}
+
+impl RemovalRule {
+    /// Returns whether rolls need to keep the face of each die instead of just the total
+    fn get_needs_dice(&self) -> bool {
+        *self != RemovalRule::SumOnly
+    }
+}
+
+impl BoxMode {
+    /// Returns whether game states can repeat, which rules out the recursive solvers
+    pub fn get_has_cycles(&self) -> bool {
+        *self == BoxMode::OpenAndShut
+    }
+}
+
+impl FromStr for BoxMode {
+    type Err = String;
+
+    /// Reads a BoxMode by its command line name
+    fn from_str(mode: &str) -> Result<BoxMode, String> {
+        match mode {
+            "shut" => Ok(BoxMode::Shut),
+            "open-and-shut" => Ok(BoxMode::OpenAndShut),
+            _ => Err(format!(
+                "Unknown mode \"{}\", expected shut or open-and-shut",
+                mode
+            )),
+        }
+    }
+}
+
+impl FromStr for RemovalRule {
+    type Err = String;
+
+    /// Reads a RemovalRule by its command line name
+    fn from_str(removal: &str) -> Result<RemovalRule, String> {
+        match removal {
+            "sum" => Ok(RemovalRule::SumOnly),
+            "individual" => Ok(RemovalRule::IndividualOnly),
+            "sum-or-individual" => Ok(RemovalRule::SumOrIndividual),
+            _ => Err(format!(
+                "Unknown removal rule \"{}\", expected sum, individual, or sum-or-individual",
+                removal
+            )),
+        }
+    }
+}
+
+impl FromStr for Combiner {
+    type Err = String;
+
+    /// Reads a Combiner by its command line name
+    fn from_str(combine: &str) -> Result<Combiner, String> {
+        match combine {
+            "sum" => Ok(Combiner::Sum),
+            "product" => Ok(Combiner::Product),
+            "difference" => Ok(Combiner::Difference),
+            "max" => Ok(Combiner::Max),
+            _ => Err(format!(
+                "Unknown combine \"{}\", expected sum, product, difference, or max",
+                combine
+            )),
+        }
+    }
+}
+
+impl FromStr for DoublesRule {
+    type Err = String;
+
+    /// Reads a DoublesRule by its command line name
+    fn from_str(doubles: &str) -> Result<DoublesRule, String> {
+        match doubles {
+            "none" => Ok(DoublesRule::NoBonus),
+            "reroll" => Ok(DoublesRule::Reroll),
+            "skip" => Ok(DoublesRule::SkipIfStuck),
+            "double" => Ok(DoublesRule::DoubleCount),
+            _ => Err(format!(
+                "Unknown doubles rule \"{}\", expected none, reroll, skip, or double",
+                doubles
+            )),
+        }
+    }
+}
+
+/// What the solvers maximize the expected value of when a game ends.
+/// Most names refer to win chance, the value of the default objective.
+#[derive(Debug, Clone)]
+enum Objective {
+    /// 1 for shutting the box, 0 otherwise
+    WinChance,
+    /// A utility for each possible score, indexed by score
+    ScoreUtility(Arc<Vec<Float>>),
+    /// The lowest expected score under a scoring, worth minus the score
+    LowScore(Scoring),
+}
+
+/// How the tiles left at the end of a game are scored, lower is better
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Scoring {
+    /// The sum of the points of the tiles left
+    PipSum,
+    /// The tiles left written one after another in increasing order, ex: 1, 4, and 7 score 147
+    Digits,
+    /// The number of tiles left
+    TileCount,
+}
+
+impl Scoring {
+    /// Returns a readable description of the scoring
+    fn get_description(&self) -> &'static str {
+        match self {
+            Scoring::PipSum => "the sum of the points of the tiles left",
+            Scoring::Digits => "the tiles left written as one number, ex: 1, 4, and 7 score 147",
+            Scoring::TileCount => "the number of tiles left",
+        }
+    }
+}
+
+impl FromStr for Scoring {
+    type Err = String;
+
+    /// Reads a Scoring by its command line name
+    fn from_str(scoring: &str) -> Result<Scoring, String> {
+        match scoring {
+            "pips" => Ok(Scoring::PipSum),
+            "digits" => Ok(Scoring::Digits),
+            "tiles" => Ok(Scoring::TileCount),
+            _ => Err(format!(
+                "Unknown scoring \"{}\", expected pips, digits, or tiles",
+                scoring
+            )),
+        }
+    }
+}
+
+/// A fixed strategy for playing a game, as opposed to the perfect play found by the solvers
+trait Policy {
+    /// Returns whether to roll a single die from the given tiles, only asked when it's legal
+    fn get_use_single(&self, tiles: &Tiles) -> bool;
+    /// Returns the chosen next state out of all legal next states for a roll
+    fn get_next_state(&self, tiles: &Tiles, next_states: &[Tiles]) -> Tiles;
+}
+
+/// Minimal necessary data to calculate a given game. Shared between all game states of a game.
+#[derive(Debug, Clone)]
+pub struct GameMeta {
+    /// The maximum value of the die
+    die_max: Uns,
+    /// The number of dice
+    die_cnt: Uns,
+    /// The starting tiles, the full board even when solving a game in progress
+    tiles: Tiles,
+    /// key: tile, value: the points it scores when left open, for tiles not scoring their label
+    tile_points: HashMap<Uns, Uns>,
+
+    /// Tile Removal Possibilities Hash Map, key: roll, value: Vec of tile combinations to remove.
+    /// This is calculated given the starting tiles, the die sides, and the max number of tiles to remove
+    trphm: HashMap<Uns, Vec<Tiles>>,
+    /// Chance of each roll if a single die is rolled
+    roll_probs_single: HashMap<Roll, Float>,
+    /// Chance of each roll if multiple dice are rolled
+    roll_probs_multi: HashMap<Roll, Float>,
+    /// Every roll of either a single die or multiple dice, sorted
+    rolls: Vec<Roll>,
+    /// Generates the legal moves of every game state, built from trphm
+    move_gen: Arc<move_gen::MoveGen>,
+
+    /// Which way tiles are flipped
+    box_mode: BoxMode,
+    /// How the dice of a roll combine into a total
+    combiner: Combiner,
+    /// Which tiles a roll allows to be flipped
+    removal_rule: RemovalRule,
+    /// Limits on which tiles may be flipped together
+    removal_limits: RemovalLimits,
+    /// What rolling doubles does
+    doubles_rule: DoublesRule,
+    /// The player may stop before getting stuck and keep the current score, but getting stuck goes bust
+    stop_early: bool,
+    /// The number of mulligans left, rerolls the player may spend after seeing a roll
+    mulligans: Uns,
+    /// Win chance of every game state with one less mulligan left, None if none are left
+    mulligan_db: Option<Arc<HashMap<Tiles, Float>>>,
+    /// What ending a game is worth
+    objective: Objective,
+    /// The match score at which a player loses a match of several rounds, 0 for a single round
+    match_limit: Uns,
+}
+
+/// Data extracted from program args (or lack thereof)
+#[derive(Debug, Clone)]
+pub struct InitData {
+    /// The sides of the given die
+    pub die_vals: Vec<Uns>,
+    /// The number of dice
+    pub die_cnt: Uns,
+    /// The starting tiles (trunk)
+    pub start_tiles: Tiles,
+    /// The tiles still open in a game in progress on the starting tiles, solved from instead of the starting tiles
+    pub open_tiles: Option<Tiles>,
+    /// The face of each die just rolled in a game in progress, to report the best move for
+    pub pending_roll: Option<Roll>,
+    /// key: tile, value: the points it scores when left open, for tiles not scoring their label
+    pub tile_points: HashMap<Uns, Uns>,
+    /// The maximum number of tiles to remove on a given turn
+    pub max_remove: Uns,
+    /// Which way tiles are flipped
+    pub box_mode: BoxMode,
+    /// How the dice of a roll combine into a total
+    pub combiner: Combiner,
+    /// Which tiles a roll allows to be flipped
+    pub removal_rule: RemovalRule,
+    /// Limits on which tiles may be flipped together
+    pub removal_limits: RemovalLimits,
+    /// What rolling doubles does
+    pub doubles_rule: DoublesRule,
+    /// The player may stop before getting stuck and keep the current score, but getting stuck goes bust
+    pub stop_early: bool,
+    /// The number of mulligans the player starts with
+    pub mulligans: Uns,
+    /// The match score at which a player loses a match of several rounds, 0 for a single round
+    pub match_limit: Uns,
+    /// Run all algos
+    pub algorithm: Algorithm,
+    /// Where to write a strategy cheat sheet, if anywhere
+    pub cheat_sheet: Option<String>,
+    /// The number of board situations listed on the cheat sheet
+    pub cheat_rows: usize,
+    /// Compare simple heuristics against perfect play
+    pub heuristics: bool,
+    /// Solve for a goal besides shutting the box, if any
+    pub utility: Option<utility::Utility>,
+    /// Solve for the lowest expected score under a scoring, if any
+    pub scoring: Option<Scoring>,
+    /// The most game states to solve before giving up
+    pub max_states: u64,
+}
+
+impl InitData {
+    /// Returns the sorted tiles to solve from, the open tiles of a game in progress or else the starting tiles
+    pub fn get_open_tiles(&self) -> Tiles {
+        get_srt(self.open_tiles.as_ref().unwrap_or(&self.start_tiles))
+    }
+}
+
+impl Default for InitData {
+    /// The classic game, matching the command line defaults: two six-sided dice and tiles 1 through 9
+    fn default() -> InitData {
+        InitData {
+            die_vals: (1..=6).collect(),
+            die_cnt: 2,
+            start_tiles: (1..=9).collect(),
+            open_tiles: None,
+            pending_roll: None,
+            tile_points: HashMap::new(),
+            max_remove: 0,
+            box_mode: BoxMode::Shut,
+            combiner: Combiner::Sum,
+            removal_rule: RemovalRule::SumOnly,
+            removal_limits: RemovalLimits::default(),
+            doubles_rule: DoublesRule::NoBonus,
+            stop_early: false,
+            mulligans: 0,
+            match_limit: 0,
+            algorithm: Algorithm::Default,
+            cheat_sheet: None,
+            cheat_rows: 20,
+            heuristics: false,
+            utility: None,
+            scoring: None,
+            max_states: 1 << 24,
+        }
+    }
+}
+
+/// Returns the game meta of the rules ready to solve from the open tiles,
+/// or why not when they have more game states than the limit or the open tiles aren't part of the board
+pub fn try_get_solvable_game_meta(init_data: &InitData) -> Result<GameMeta, String> {
+    let game_meta = try_get_checked_game_meta(init_data)?;
+    let (game_meta, _) = get_mulligan_game_meta_from(game_meta, &init_data.get_open_tiles());
+    Ok(game_meta)
+}
+
+/// Returns the game meta of the rules before solving for mulligans, or why the rules can't be solved
+pub fn try_get_checked_game_meta(init_data: &InitData) -> Result<GameMeta, String> {
+    if !get_totals_fit(&init_data.die_vals, init_data.die_cnt) {
+        return Err(format!(
+            "Rolls of {} dice with faces up to {} can total more than {}",
+            init_data.die_cnt,
+            init_data.die_vals.iter().max().unwrap_or(&0),
+            Uns::MAX
+        ));
+    }
+    if !get_scores_fit(&init_data.start_tiles, &init_data.tile_points) {
+        return Err(format!(
+            "The points of the board {:?} add up to more than {}",
+            init_data.start_tiles, MAX_SCORE
+        ));
+    }
+    // checked before setting up the moves, which takes time and memory growing with the game states.
+    // The game is solved again for each number of mulligans left
+    let state_cnt = get_max_state_cnt(init_data).saturating_mul(init_data.mulligans as u64 + 1);
+    if state_cnt > init_data.max_states {
+        return Err(format!(
+            "These rules have up to {} game states, more than the limit of {}. \
+             Raise --max-states if there is enough memory, each game state takes roughly 100 bytes",
+            state_cnt, init_data.max_states
+        ));
+    }
+    let game_meta = get_game_meta(init_data)?;
+    let open_tiles = init_data.get_open_tiles();
+    if get_removed_tiles(&game_meta.tiles, &open_tiles).is_none() {
+        return Err(format!(
+            "Open tiles {:?} aren't part of the board {:?}",
+            open_tiles, game_meta.tiles
+        ));
+    }
+    Ok(game_meta)
+}
+
+/// Returns whether every total the dice can roll fits in an Uns, even when doubles count twice
+fn get_totals_fit(die_vals: &[Uns], die_cnt: Uns) -> bool {
+    let max_face = die_vals.iter().copied().max().unwrap_or(0);
+    max_face
+        .checked_mul(die_cnt)
+        .and_then(|total| total.checked_mul(2))
+        .is_some()
+}
+
+/// Returns whether the points of every tile of the board add up to at most MAX_SCORE, so any score fits
+fn get_scores_fit(start_tiles: &Tiles, tile_points: &HashMap<Uns, Uns>) -> bool {
+    start_tiles
+        .iter()
+        .try_fold(0 as Uns, |total, tile| {
+            total.checked_add(*tile_points.get(tile).unwrap_or(tile))
+        })
+        .is_some_and(|total| total <= MAX_SCORE)
+}
+
+/// Returns the game meta for a game with mulligans, solving the same game with fewer mulligans left along the way
+pub fn get_mulligan_game_meta(game_meta: GameMeta) -> GameMeta {
+    let tiles = game_meta.tiles.clone();
+    get_mulligan_game_meta_from(game_meta, &tiles).0
+}
+
+/// Returns the game meta for a game with mulligans played from the given tiles,
+/// solving the same game with fewer mulligans left along the way,
+/// and the win chance of the tiles with each number of mulligans solved along the way
+pub fn get_mulligan_game_meta_from(game_meta: GameMeta, tiles: &Tiles) -> (GameMeta, Vec<Float>) {
+    let mut mulligan_db = None;
+    let mut win_chances = Vec::new();
+    for mulligans in 0..game_meta.mulligans {
+        let trunk = get_trunk_from(
+            GameMeta {
+                mulligans,
+                mulligan_db,
+                ..game_meta.clone()
+            },
+            tiles,
+        );
+        win_chances.push(trunk.game_db[tiles]);
+        mulligan_db = Some(Arc::new(trunk.game_db));
+    }
+    (
+        GameMeta {
+            mulligan_db,
+            ..game_meta
+        },
+        win_chances,
+    )
+}
+
+/// Returns the game meta of the same rules played for the lowest expected score under a scoring instead of winning.
+/// Each game state is then worth minus its expected score. Mulligans are solved again by get_mulligan_game_meta.
+pub fn get_scoring_game_meta(game_meta: GameMeta, scoring: Scoring) -> GameMeta {
+    GameMeta {
+        objective: Objective::LowScore(scoring),
+        mulligan_db: None,
+        ..game_meta
+    }
+}
+
+/// Solves a given game for an objective, solving the same game with fewer mulligans left along the way
+fn get_objective_trunk(game_meta: &GameMeta, objective: Objective) -> Trunk {
+    let mut mulligan_db = None;
+    for mulligans in 0..game_meta.mulligans {
+        let trunk = get_trunk(GameMeta {
+            mulligans,
+            mulligan_db,
+            objective: objective.clone(),
+            ..game_meta.clone()
+        });
+        mulligan_db = Some(Arc::new(trunk.game_db));
+    }
+    get_trunk(GameMeta {
+        mulligan_db,
+        objective,
+        ..game_meta.clone()
+    })
+}
+
+/// Solves a given game, keeping every solved game state
+pub fn get_trunk(game_meta: GameMeta) -> Trunk {
+    let tiles = game_meta.tiles.clone();
+    get_trunk_from(game_meta, &tiles)
+}
+
+/// Solves a given game from a game state, such as a game in progress, keeping every solved game state.
+/// Only the game states reachable from the given tiles are solved, unless game states can repeat.
+pub fn get_trunk_from(game_meta: GameMeta, tiles: &Tiles) -> Trunk {
+    let game_db = if game_meta.box_mode.get_has_cycles() {
+        value_solve(&game_meta)
+    } else {
+        let mut game_db = HashMap::new();
+        depth_solve(tiles.clone(), &game_meta, &mut game_db);
+        game_db
+    };
+    Trunk { game_meta, game_db }
+}
+
+/// Solves a given game in parallel, one number of tiles left at a time.
+/// Every move flips at least one tile, so each level only reads the already solved levels below it.
+#[cfg(feature = "parallel")]
+pub fn par_solve(tiles: Tiles, game_meta: GameMeta) -> HashMap<Tiles, Float> {
+    // find every reachable game state, from the most tiles left down, key: number of tiles left
+    let mut levels: Vec<HashSet<Tiles>> = vec![HashSet::new(); tiles.len() + 1];
+    levels[tiles.len()].insert(tiles.clone());
+    for tile_cnt in (1..=tiles.len()).rev() {
+        let next_states: Vec<Tiles> = levels[tile_cnt]
+            .par_iter()
+            .flat_map_iter(|tiles| {
+                get_next_legal_states_all(tiles, &game_meta)
+                    .into_values()
+                    .flatten()
+            })
+            .collect();
+        for next_state in next_states {
+            levels[next_state.len()].insert(next_state);
+        }
+    }
+
+    let mut game_db = HashMap::new();
+    for level in levels {
+        let solved_level: Vec<(Tiles, Float)> = level
+            .into_par_iter()
+            .map(|tiles| {
+                let win_chance = get_solved_win_chance(&tiles, &game_meta, &game_db);
+                (tiles, win_chance)
+            })
+            .collect();
+        game_db.extend(solved_level);
+    }
+    game_db
+}
+
+/// Returns the win chance of a game state when every next state is already solved in game_db
+#[cfg(feature = "parallel")]
+fn get_solved_win_chance(
+    tiles: &Tiles,
+    game_meta: &GameMeta,
+    game_db: &HashMap<Tiles, Float>,
+) -> Float {
+    if tiles.is_empty() {
+        return get_end_value(tiles, game_meta);
+    }
+    let best_states_hm = get_solved_best_states(tiles, game_meta, game_db);
+    let (win_chance_single, win_chance_multi) =
+        get_dice_win_chances(tiles, &best_states_hm, game_meta);
+    get_state_win_chance(tiles, win_chance_single, win_chance_multi, game_meta)
+}
+
+/// Returns a readable String of the given GameMeta
+#[allow(dead_code)]
+fn get_readable_game_meta(game_meta: &GameMeta) -> String {
+    let mut out = String::new();
+    out.push_str(&format!("    die_max: {}\n", game_meta.die_max));
+    out.push_str(&format!("    start_tiles: {:?}\n", game_meta.tiles));
+    out.push_str(&format!("    trphm: {:?}\n", game_meta.trphm));
+    out.push_str(&format!(
+        "    roll_probs_single: {:?}\n",
+        game_meta.roll_probs_single
+    ));
+    out.push_str(&format!(
+        "    roll_probs_multi: {:?}\n",
+        game_meta.roll_probs_multi
+    ));
+    out
+}
+
+/// Returns a readable String of the given Trunk
+#[allow(dead_code)]
+fn get_readable_trunk_string(trunk: &Trunk) -> String {
+    let mut s = String::new();
+    s.push_str("  Game Meta:\n");
+    s.push_str(&get_readable_game_meta(&trunk.game_meta));
+    s.push_str("\nWIN CHANCE:\n");
+    s.push_str(&format!(
+        "  {:?}",
+        trunk.game_db.get(&trunk.game_meta.tiles).unwrap()
+    ));
+    s.push('\n');
+    s
+}
+
+/// Recursively and naively solves a given game through a breadth-first traversal
+pub fn naive_solve(tiles: Tiles, game_meta: &GameMeta) -> (Float, u64) {
+    let (win_single, game_calc_cnt_single) = naive_solve_single(tiles.clone(), game_meta);
+    let (win_multi, game_calc_cnt_multi) = naive_solve_multi(tiles.clone(), game_meta);
+    (
+        get_state_win_chance(&tiles, win_single, win_multi, game_meta),
+        game_calc_cnt_single + game_calc_cnt_multi,
+    )
+}
+
+fn naive_solve_single(tiles: Tiles, game_meta: &GameMeta) -> (Float, u64) {
+    if tiles.is_empty() {
+        return (get_end_value(&tiles, game_meta), 0);
+    }
+    // an illegal single die roll must never look better than rolling every die
+    if !get_single_legality(&tiles, &game_meta.die_max) {
+        return (Float::NEG_INFINITY, 0);
+    }
+    let mut prob = 0.;
+    let roll_probs = &game_meta.roll_probs_single;
+    let mulligan_win_chance = get_mulligan_win_chance(&tiles, game_meta);
+    let stuck_win_chance = get_stuck_win_chance(&tiles, game_meta);
+    let mut game_count = 1;
+    for (roll, roll_prob) in roll_probs {
+        let mut rolls = Vec::new();
+        for new_tiles in get_next_legal_states_roll(&tiles, roll, game_meta) {
+            let (win_chance, new_game_count) = naive_solve(new_tiles, game_meta);
+            game_count += new_game_count;
+            rolls.push(win_chance);
+        }
+        let best_win_chance = rolls.iter().cloned().reduce(Float::max);
+        prob += roll_prob
+            * get_roll_win_chance(
+                roll,
+                best_win_chance,
+                stuck_win_chance,
+                0.,
+                mulligan_win_chance,
+                game_meta,
+            );
+    }
+    (prob, game_count)
+}
+
+fn naive_solve_multi(tiles: Tiles, game_meta: &GameMeta) -> (Float, u64) {
+    if tiles.is_empty() {
+        return (get_end_value(&tiles, game_meta), 0);
+    }
+    let mut prob = 0.;
+    let roll_probs = &game_meta.roll_probs_multi;
+    let mulligan_win_chance = get_mulligan_win_chance(&tiles, game_meta);
+    let stuck_win_chance = get_stuck_win_chance(&tiles, game_meta);
+    let mut game_count = 1;
+    for (roll, roll_prob) in roll_probs {
+        let mut rolls = Vec::new();
+        for new_tiles in get_next_legal_states_roll(&tiles, roll, game_meta) {
+            let (win_chance, new_game_count) = naive_solve(new_tiles, game_meta);
+            game_count += new_game_count;
+            rolls.push(win_chance);
+        }
+        let best_win_chance = rolls.iter().cloned().reduce(Float::max);
+        prob += roll_prob
+            * get_roll_win_chance(
+                roll,
+                best_win_chance,
+                stuck_win_chance,
+                0.,
+                mulligan_win_chance,
+                game_meta,
+            );
+    }
+    (prob, game_count)
+}
+
+/// Recursively solves a given game through a depth-first traversal
+pub fn depth_solve(
+    tiles: Tiles,
+    game_meta: &GameMeta,
+    game_db: &mut HashMap<Tiles, Float>,
+) -> Float {
+    if let Some(existing_game) = game_db.get(&tiles) {
+        return *existing_game;
+    }
+    if tiles.is_empty() {
+        let win_chance = get_end_value(&tiles, game_meta);
+        game_db.insert(tiles, win_chance);
+        return win_chance;
+    }
+    let all_next_legal_states_hm = get_next_legal_states_all(&tiles, game_meta);
+    let solved_next_legal_states_hm =
+        get_all_stats_from_hm(&all_next_legal_states_hm, game_meta, game_db);
+    let best_states_hm = get_best_states(solved_next_legal_states_hm);
+
+    let (win_chance_single, win_chance_multi) =
+        get_dice_win_chances(&tiles, &best_states_hm, game_meta);
+    let win_chance = get_state_win_chance(&tiles, win_chance_single, win_chance_multi, game_meta);
+    game_db.insert(tiles, win_chance);
+    win_chance
+}
+
+/// Recursively finds the win chance of a given game when following a policy instead of playing perfectly.
+/// Visits the same game states as depth_solve. Policies never spend a mulligan, so any the rules give are left unused.
+fn policy_solve(
+    tiles: Tiles,
+    game_meta: &GameMeta,
+    policy: &dyn Policy,
+    game_db: &mut HashMap<Tiles, Float>,
+) -> Float {
+    if let Some(existing_game) = game_db.get(&tiles) {
+        return *existing_game;
+    }
+    if tiles.is_empty() {
+        let win_chance = get_end_value(&tiles, game_meta);
+        game_db.insert(tiles, win_chance);
+        return win_chance;
+    }
+    let all_next_legal_states_hm = get_next_legal_states_all(&tiles, game_meta);
+    let roll_probs =
+        if get_single_legality(&tiles, &game_meta.die_max) && policy.get_use_single(&tiles) {
+            &game_meta.roll_probs_single
+        } else {
+            &game_meta.roll_probs_multi
+        };
+
+    // policies never spend a free reroll, but a stuck roll that must be rolled again is rolled again
+    let stuck_win_chance = get_stuck_win_chance(&tiles, game_meta);
+    let mut win_chance = 0.;
+    let mut roll_again_chance = 0.;
+    for (roll, roll_prob) in roll_probs {
+        if let Some(next_states) = all_next_legal_states_hm.get(roll) {
+            let next_state = policy.get_next_state(&tiles, next_states);
+            win_chance += roll_prob * policy_solve(next_state, game_meta, policy, game_db);
+        } else if game_meta.doubles_rule == DoublesRule::SkipIfStuck && get_is_doubles(roll) {
+            roll_again_chance += roll_prob;
+        } else {
+            win_chance += roll_prob * stuck_win_chance;
+        }
+    }
+    if roll_again_chance < 1. {
+        win_chance /= 1. - roll_again_chance;
+    }
+    game_db.insert(tiles, win_chance);
+    win_chance
+}
+
+/// Every game state of a game and the legal moves between them, for solvers that sweep over every state
+struct StateGraph {
+    /// Every game state, closest to shutting the box first
+    states: Vec<Tiles>,
+    /// key: game state, value: its index in states
+    state_ids: HashMap<Tiles, usize>,
+    /// key: roll, value: ids of the next legal states, for every state
+    next_state_ids: Vec<Vec<(Roll, Vec<usize>)>>,
+}
+
+/// Returns every game state of a given game and the legal moves between them
+fn get_state_graph(game_meta: &GameMeta) -> StateGraph {
+    let flippable_tiles = get_flippable_tiles(game_meta);
+    let fixed_tiles = get_removed_tiles(&game_meta.tiles, &flippable_tiles).unwrap();
+    let mut states: Vec<Tiles> = get_all_subsets(&flippable_tiles)
+        .into_iter()
+        .map(|mut tiles| {
+            tiles.extend(&fixed_tiles);
+            tiles.sort_unstable();
+            tiles
+        })
+        .collect();
+    // closest to shutting the box first, so wins spread through each sweep faster
+    states.sort_by_key(|tiles| tiles.len());
+    let state_ids: HashMap<Tiles, usize> = states
+        .iter()
+        .enumerate()
+        .map(|(id, tiles)| (tiles.clone(), id))
+        .collect();
+
+    let next_state_ids = states
+        .iter()
+        .map(|tiles| {
+            get_next_legal_states_all(tiles, game_meta)
+                .into_iter()
+                .map(|(roll, next_states)| {
+                    let ids = next_states.iter().map(|state| state_ids[state]).collect();
+                    (roll, ids)
+                })
+                .collect()
+        })
+        .collect();
+    StateGraph {
+        states,
+        state_ids,
+        next_state_ids,
+    }
+}
+
+/// Solves a given game through value iteration over every game state.
+/// Slower than the recursive solvers, but also handles rules where game states can repeat.
+pub fn value_solve(game_meta: &GameMeta) -> HashMap<Tiles, Float> {
+    let graph = get_state_graph(game_meta);
+    let win_chances = graph_solve(&graph, game_meta);
+    graph.states.into_iter().zip(win_chances).collect()
+}
+
+/// Returns the win chance of every game state in a state graph, in the same order, through value iteration
+fn graph_solve(graph: &StateGraph, game_meta: &GameMeta) -> Vec<Float> {
+    let states = &graph.states;
+    // start every unfinished game at the worst possible outcome, so values only climb towards perfect play
+    let min_end_value = states
+        .iter()
+        .map(|tiles| get_end_value(tiles, game_meta))
+        .fold(Float::INFINITY, Float::min);
+    let mut win_chances: Vec<Float> = states
+        .iter()
+        .map(|tiles| {
+            if tiles.is_empty() {
+                get_end_value(tiles, game_meta)
+            } else {
+                min_end_value
+            }
+        })
+        .collect();
+    for _ in 0..VALUE_ITERATION_MAX_SWEEPS {
+        let mut max_change: Float = 0.;
+        for id in 0..states.len() {
+            if states[id].is_empty() {
+                continue;
+            }
+            let best_win_chance = |roll: &Roll| {
+                graph.next_state_ids[id]
+                    .iter()
+                    .find(|(next_roll, _)| next_roll == roll)
+                    .map(|(_, ids)| {
+                        ids.iter()
+                            .map(|next_id| win_chances[*next_id])
+                            .fold(Float::NEG_INFINITY, Float::max)
+                    })
+            };
+            let (win_chance_single, win_chance_multi) =
+                get_roll_win_chances(&states[id], &best_win_chance, win_chances[id], game_meta);
+            let win_chance =
+                get_state_win_chance(&states[id], win_chance_single, win_chance_multi, game_meta);
+            max_change = max_change.max((win_chance - win_chances[id]).abs());
+            win_chances[id] = win_chance;
+        }
+        if max_change < VALUE_ITERATION_TOLERANCE {
+            break;
+        }
+    }
+    win_chances
+}
+
+/// Returns the starting tiles that some roll can flip, the only tiles that can change during a game
+fn get_flippable_tiles(game_meta: &GameMeta) -> Tiles {
+    game_meta
+        .tiles
+        .iter()
+        .copied()
+        .filter(|tile| {
+            game_meta
+                .trphm
+                .values()
+                .any(|trps| trps.iter().any(|trp| trp.contains(tile)))
+        })
+        .collect()
+}
+
+/// Returns the most game states a game can reach, every distinct subset of the tiles that can be flipped
+pub fn get_state_cnt(game_meta: &GameMeta) -> u64 {
+    get_subset_cnt(&get_flippable_tiles(game_meta))
+}
+
+/// Returns the most game states the rules can reach without setting up their moves, never less than get_state_cnt.
+/// Counts every starting tile no higher than the highest roll total as flippable,
+/// or only the tiles equal to a roll total when a single tile is removed per turn.
+pub fn get_max_state_cnt(init_data: &InitData) -> u64 {
+    let (roll_probs_single, roll_probs_multi) = get_roll_probs_both(init_data);
+    let rolls = get_srt_dedup_keys(&roll_probs_multi, &roll_probs_single);
+    let totals = get_srt_dedup_totals(&rolls, init_data.combiner, init_data.doubles_rule);
+    let max_total = totals.last().copied().unwrap_or(0);
+    let flippable_tiles: Tiles = init_data
+        .start_tiles
+        .iter()
+        .copied()
+        .filter(|tile| {
+            if init_data.max_remove == 1 {
+                totals.binary_search(tile).is_ok()
+            } else {
+                *tile <= max_total
+            }
+        })
+        .collect();
+    get_subset_cnt(&flippable_tiles)
+}
+
+/// Returns the number of distinct subsets of the given tiles
+fn get_subset_cnt(tiles: &Tiles) -> u64 {
+    let mut tile_cnts: HashMap<Uns, u64> = HashMap::new();
+    for tile in tiles {
+        *tile_cnts.entry(*tile).or_insert(0) += 1;
+    }
+    tile_cnts.values().fold(1, |state_cnt: u64, tile_cnt| {
+        state_cnt.saturating_mul(tile_cnt + 1)
+    })
+}
+
+/// Returns every distinct subset of the given tiles, each sorted
+fn get_all_subsets(tiles: &Tiles) -> Vec<Tiles> {
+    let mut subsets = vec![Vec::new()];
+    for tile in get_srt(tiles) {
+        let mut with_tile: Vec<Tiles> = subsets
+            .iter()
+            .map(|subset| {
+                let mut subset = subset.clone();
+                subset.push(tile);
+                subset
+            })
+            .collect();
+        subsets.append(&mut with_tile);
+    }
+    subsets.sort_unstable();
+    subsets.dedup();
+    subsets
+}
+
+/// Returns the best next state and its win chance for each roll, given every solved next legal state
+fn get_best_states(
+    solved_states_hm: HashMap<Roll, Vec<(Tiles, Float)>>,
+) -> HashMap<Roll, (Tiles, Float)> {
+    let mut best_states_hm = HashMap::new();
+    for (roll, state_stat) in solved_states_hm {
+        let mut best: Option<(Tiles, Float)> = None;
+        for (state, win_chance) in state_stat {
+            match &best {
+                Some((_, best_win_chance)) if *best_win_chance >= win_chance => {}
+                _ => best = Some((state, win_chance)),
+            }
+        }
+        if let Some(best) = best {
+            best_states_hm.insert(roll, best);
+        }
+    }
+    best_states_hm
+}
+
+/// Returns the best next state and its win chance for each roll using an already solved game_db
+fn get_solved_best_states(
+    tiles: &Tiles,
+    game_meta: &GameMeta,
+    game_db: &HashMap<Tiles, Float>,
+) -> HashMap<Roll, (Tiles, Float)> {
+    let mut solved_states_hm = HashMap::new();
+    for (roll, states) in get_next_legal_states_all(tiles, game_meta) {
+        let solved_states = states
+            .into_iter()
+            .map(|state| {
+                let win_chance = *game_db.get(&state).unwrap();
+                (state, win_chance)
+            })
+            .collect();
+        solved_states_hm.insert(roll, solved_states);
+    }
+    get_best_states(solved_states_hm)
+}
+
+/// Returns the win chance when rolling a single die and when rolling multiple dice (single is negative infinity if not legal)
+fn get_dice_win_chances(
+    tiles: &Tiles,
+    best_states_hm: &HashMap<Roll, (Tiles, Float)>,
+    game_meta: &GameMeta,
+) -> (Float, Float) {
+    let best_win_chance = |roll: &Roll| best_states_hm.get(roll).map(|(_, win_chance)| *win_chance);
+    let mut win_chances = get_roll_win_chances(tiles, &best_win_chance, 0., game_meta);
+    if !game_meta.doubles_rule.get_rolls_again() {
+        return win_chances;
+    }
+    // rolling again is worth this state's own win chance, so iterate until that settles
+    for _ in 0..VALUE_ITERATION_MAX_SWEEPS {
+        let reroll_win_chance =
+            get_state_win_chance(tiles, win_chances.0, win_chances.1, game_meta);
+        win_chances = get_roll_win_chances(tiles, &best_win_chance, reroll_win_chance, game_meta);
+        let win_chance = get_state_win_chance(tiles, win_chances.0, win_chances.1, game_meta);
+        if (win_chance - reroll_win_chance).abs() < VALUE_ITERATION_TOLERANCE {
+            break;
+        }
+    }
+    win_chances
+}
+
+/// Returns the win chance when rolling a single die and when rolling multiple dice (single is negative infinity if not legal),
+/// given the best win chance after each roll with a legal move and the win chance of rolling again
+fn get_roll_win_chances(
+    tiles: &Tiles,
+    best_win_chance: &dyn Fn(&Roll) -> Option<Float>,
+    reroll_win_chance: Float,
+    game_meta: &GameMeta,
+) -> (Float, Float) {
+    let mulligan_win_chance = get_mulligan_win_chance(tiles, game_meta);
+    let stuck_win_chance = get_stuck_win_chance(tiles, game_meta);
+    // an illegal single die roll must never look better than rolling every die, whatever the objective
+    let mut win_chance_single = Float::NEG_INFINITY;
+    if get_single_legality(tiles, &game_meta.die_max) {
+        win_chance_single = 0.;
+        for (roll, single_chance) in &game_meta.roll_probs_single {
+            let win_chance = get_roll_win_chance(
+                roll,
+                best_win_chance(roll),
+                stuck_win_chance,
+                reroll_win_chance,
+                mulligan_win_chance,
+                game_meta,
+            );
+            win_chance_single += win_chance * single_chance;
+        }
+    }
+    let mut win_chance_multi = 0.;
+    for (roll, multi_chance) in &game_meta.roll_probs_multi {
+        let win_chance = get_roll_win_chance(
+            roll,
+            best_win_chance(roll),
+            stuck_win_chance,
+            reroll_win_chance,
+            mulligan_win_chance,
+            game_meta,
+        );
+        win_chance_multi += win_chance * multi_chance;
+    }
+    (win_chance_single, win_chance_multi)
+}
+
+/// Returns the win chance of a game state given the win chance of rolling a single die and multiple dice,
+/// counting stopping early when it's allowed
+fn get_state_win_chance(
+    tiles: &Tiles,
+    win_chance_single: Float,
+    win_chance_multi: Float,
+    game_meta: &GameMeta,
+) -> Float {
+    let win_chance = win_chance_single.max(win_chance_multi);
+    match get_stop_win_chance(tiles, game_meta) {
+        Some(stop_win_chance) => win_chance.max(stop_win_chance),
+        None => win_chance,
+    }
+}
+
+/// Returns whether perfect play stops early from a game state instead of rolling
+fn get_stops(
+    tiles: &Tiles,
+    win_chance_single: Float,
+    win_chance_multi: Float,
+    game_meta: &GameMeta,
+) -> bool {
+    match get_stop_win_chance(tiles, game_meta) {
+        Some(stop_win_chance) => stop_win_chance > win_chance_single.max(win_chance_multi),
+        None => false,
+    }
+}
+
+/// Returns the win chance of stopping early with the given tiles, None if stopping isn't allowed
+fn get_stop_win_chance(tiles: &Tiles, game_meta: &GameMeta) -> Option<Float> {
+    if game_meta.stop_early && !tiles.is_empty() {
+        Some(get_end_value(tiles, game_meta))
+    } else {
+        None
+    }
+}
+
+/// Returns the win chance of getting stuck with the given tiles.
+/// When stopping early is allowed getting stuck goes bust, which scores as if no tile was flipped.
+fn get_stuck_win_chance(tiles: &Tiles, game_meta: &GameMeta) -> Float {
+    if game_meta.stop_early {
+        get_end_value(&game_meta.tiles, game_meta)
+    } else {
+        get_end_value(tiles, game_meta)
+    }
+}
+
+/// Returns the win chance after a roll given the best win chance from flipping tiles (None if stuck),
+/// the win chance of being stuck, the win chance of rolling again for free,
+/// and the win chance of spending a mulligan (None if none are left)
+fn get_roll_win_chance(
+    roll: &Roll,
+    best_win_chance: Option<Float>,
+    stuck_win_chance: Float,
+    reroll_win_chance: Float,
+    mulligan_win_chance: Option<Float>,
+    game_meta: &GameMeta,
+) -> Float {
+    let win_chance = if get_is_doubles(roll) {
+        match game_meta.doubles_rule {
+            DoublesRule::Reroll => best_win_chance
+                .unwrap_or(stuck_win_chance)
+                .max(reroll_win_chance),
+            DoublesRule::SkipIfStuck => best_win_chance.unwrap_or(reroll_win_chance),
+            DoublesRule::NoBonus | DoublesRule::DoubleCount => {
+                best_win_chance.unwrap_or(stuck_win_chance)
+            }
+        }
+    } else {
+        best_win_chance.unwrap_or(stuck_win_chance)
+    };
+    match mulligan_win_chance {
+        Some(mulligan_win_chance) => win_chance.max(mulligan_win_chance),
+        None => win_chance,
+    }
+}
+
+/// Returns the win chance of spending a mulligan from the given tiles, None if none are left.
+/// Also None for game states the game can't reach, which value iteration still sweeps over.
+fn get_mulligan_win_chance(tiles: &Tiles, game_meta: &GameMeta) -> Option<Float> {
+    game_meta
+        .mulligan_db
+        .as_ref()
+        .and_then(|mulligan_db| mulligan_db.get(tiles).copied())
+}
+
+/// Returns whether perfect play after a roll is to spend a mulligan instead of anything else
+fn get_spends_mulligan(
+    tiles: &Tiles,
+    roll: &Roll,
+    best_states_hm: &HashMap<Roll, (Tiles, Float)>,
+    win_chance: Float,
+    game_meta: &GameMeta,
+) -> bool {
+    match get_mulligan_win_chance(tiles, game_meta) {
+        Some(mulligan_win_chance) => {
+            let best_win_chance = best_states_hm.get(roll).map(|(_, win_chance)| *win_chance);
+            let stuck_win_chance = get_stuck_win_chance(tiles, game_meta);
+            mulligan_win_chance
+                > get_roll_win_chance(
+                    roll,
+                    best_win_chance,
+                    stuck_win_chance,
+                    win_chance,
+                    None,
+                    game_meta,
+                )
+        }
+        None => false,
+    }
+}
+
+/// Returns whether perfect play after a roll is to roll again from the same game state instead of flipping tiles
+fn get_rolls_again(
+    roll: &Roll,
+    best_states_hm: &HashMap<Roll, (Tiles, Float)>,
+    win_chance: Float,
+    game_meta: &GameMeta,
+) -> bool {
+    if !get_is_doubles(roll) {
+        return false;
+    }
+    match (game_meta.doubles_rule, best_states_hm.get(roll)) {
+        (DoublesRule::Reroll, Some((_, best_win_chance))) => win_chance > *best_win_chance,
+        (DoublesRule::Reroll | DoublesRule::SkipIfStuck, None) => true,
+        _ => false,
+    }
+}
+
+/// Returns whether a roll is doubles, every die showing the same face
+fn get_is_doubles(roll: &Roll) -> bool {
+    roll.len() > 1 && roll.iter().all(|die| *die == roll[0])
+}
+
+/// Returns whether rolls keep the face of each die instead of just their total, when the rules need the faces
+fn get_keeps_dice(removal_rule: RemovalRule, doubles_rule: DoublesRule) -> bool {
+    removal_rule.get_needs_dice() || doubles_rule != DoublesRule::NoBonus
+}
+
+/// Returns the roll the given dice count as, their sorted faces if rolls keep them, otherwise their total
+fn get_dice_roll(dice: &[Uns], game_meta: &GameMeta) -> Roll {
+    if get_keeps_dice(game_meta.removal_rule, game_meta.doubles_rule) {
+        get_srt(dice)
+    } else {
+        vec![game_meta.combiner.get_total(dice)]
+    }
+}
+
+/// Returns the total a roll counts as
+fn get_roll_total(roll: &Roll, combiner: Combiner, doubles_rule: DoublesRule) -> Uns {
+    let total = combiner.get_total(roll);
+    if doubles_rule == DoublesRule::DoubleCount && get_is_doubles(roll) {
+        total.saturating_mul(2)
+    } else {
+        total
+    }
+}
+
+/// Returns the chance of moving to each next game state when playing perfectly from a game state of a solved game,
+/// the chance of the game ending there instead, and the chance of getting stuck there when that goes bust
+fn get_next_state_chances(tiles: &Tiles, trunk: &Trunk) -> (HashMap<Tiles, Float>, Float, Float) {
+    let game_meta = &trunk.game_meta;
+    let mut next_state_chances: HashMap<Tiles, Float> = HashMap::new();
+    if tiles.is_empty() {
+        return (next_state_chances, 1., 0.);
+    }
+    let best_states_hm = get_solved_best_states(tiles, game_meta, &trunk.game_db);
+    let (win_chance_single, win_chance_multi) =
+        get_dice_win_chances(tiles, &best_states_hm, game_meta);
+    if get_stops(tiles, win_chance_single, win_chance_multi, game_meta) {
+        return (next_state_chances, 1., 0.);
+    }
+    let roll_probs = if win_chance_single > win_chance_multi {
+        &game_meta.roll_probs_single
+    } else {
+        &game_meta.roll_probs_multi
+    };
+    // rolling again from the same state just spreads the same chance over the other rolls
+    let win_chance = get_state_win_chance(tiles, win_chance_single, win_chance_multi, game_meta);
+    // spending a mulligan is treated the same, though the rest of that game has one less mulligan
+    let rolls_again = |roll: &Roll| {
+        get_rolls_again(roll, &best_states_hm, win_chance, game_meta)
+            || get_spends_mulligan(tiles, roll, &best_states_hm, win_chance, game_meta)
+    };
+    let roll_again_chance: Float = roll_probs
+        .iter()
+        .filter(|(roll, _)| rolls_again(roll))
+        .map(|(_, roll_prob)| roll_prob)
+        .sum();
+    if roll_again_chance >= 1. {
+        return (next_state_chances, 0., 0.);
+    }
+    let mut stuck_chance = 0.;
+    for (roll, roll_prob) in roll_probs {
+        if rolls_again(roll) {
+            continue;
+        }
+        let roll_chance = roll_prob / (1. - roll_again_chance);
+        match best_states_hm.get(roll) {
+            Some((state, _)) => {
+                *next_state_chances.entry(state.clone()).or_insert(0.) += roll_chance;
+            }
+            None => stuck_chance += roll_chance,
+        }
+    }
+    if game_meta.stop_early {
+        (next_state_chances, 0., stuck_chance)
+    } else {
+        (next_state_chances, stuck_chance, 0.)
+    }
+}
+
+/// Returns the chance of reaching each game state of a solved game when playing perfectly from the start
+fn get_reach_chances(trunk: &Trunk) -> HashMap<Tiles, Float> {
+    get_reach_chances_from(&trunk.game_meta.tiles, trunk)
+}
+
+/// Returns the chance of reaching each game state of a solved game when playing perfectly from the given tiles
+fn get_reach_chances_from(start_tiles: &Tiles, trunk: &Trunk) -> HashMap<Tiles, Float> {
+    let mut states: Vec<&Tiles> = trunk.game_db.keys().collect();
+    // every move removes at least one tile, so parents always come before their children
+    states.sort_by_key(|tiles| std::cmp::Reverse(tiles.len()));
+
+    let mut reach_chances: HashMap<Tiles, Float> = HashMap::new();
+    reach_chances.insert(start_tiles.clone(), 1.);
+    for tiles in states {
+        let reach_chance = match reach_chances.get(tiles) {
+            Some(reach_chance) if *reach_chance > 0. => *reach_chance,
+            _ => continue,
+        };
+        let (next_state_chances, _, _) = get_next_state_chances(tiles, trunk);
+        for (state, chance) in next_state_chances {
+            *reach_chances.entry(state).or_insert(0.) += reach_chance * chance;
+        }
+    }
+    reach_chances
+}
+
+/// Returns the chance of a solved game being scored with each game state when playing perfectly from the start.
+/// Going bust is scored with the starting tiles.
+fn get_end_chances(trunk: &Trunk) -> HashMap<Tiles, Float> {
+    get_end_chances_from(&trunk.game_meta.tiles, trunk)
+}
+
+/// Returns the chance of a solved game being scored with each game state when playing perfectly from the given tiles
+fn get_end_chances_from(start_tiles: &Tiles, trunk: &Trunk) -> HashMap<Tiles, Float> {
+    let mut end_chances: HashMap<Tiles, Float> = HashMap::new();
+    for (tiles, reach_chance) in get_reach_chances_from(start_tiles, trunk) {
+        let (_, end_chance, bust_chance) = get_next_state_chances(&tiles, trunk);
+        if bust_chance > 0. {
+            *end_chances
+                .entry(trunk.game_meta.tiles.clone())
+                .or_insert(0.) += reach_chance * bust_chance;
+        }
+        if end_chance > 0. {
+            *end_chances.entry(tiles).or_insert(0.) += reach_chance * end_chance;
+        }
+    }
+    end_chances
+}
+
+/// Returns the chance of each final score of a solved game when playing perfectly from the start, indexed by score
+fn get_score_chances(trunk: &Trunk) -> Vec<Float> {
+    let game_meta = &trunk.game_meta;
+    let mut score_chances = vec![0.; get_score(&game_meta.tiles, game_meta) as usize + 1];
+    for (tiles, end_chance) in get_end_chances(trunk) {
+        score_chances[get_score(&tiles, game_meta) as usize] += end_chance;
+    }
+    score_chances
+}
+
+/// Returns the score of a game ending with the given tiles, the sum of the points of the tiles left
+fn get_score(tiles: &Tiles, game_meta: &GameMeta) -> Uns {
+    tiles
+        .iter()
+        .map(|tile| get_tile_points(*tile, game_meta))
+        .sum()
+}
+
+/// Returns the points a tile scores when left open, its label unless it was given other points
+fn get_tile_points(tile: Uns, game_meta: &GameMeta) -> Uns {
+    *game_meta.tile_points.get(&tile).unwrap_or(&tile)
+}
+
+/// Returns what ending a game with the given tiles is worth
+fn get_end_value(tiles: &Tiles, game_meta: &GameMeta) -> Float {
+    match &game_meta.objective {
+        Objective::WinChance => {
+            if tiles.is_empty() {
+                1.
+            } else {
+                0.
+            }
+        }
+        Objective::ScoreUtility(utilities) => utilities[get_score(tiles, game_meta) as usize],
+        Objective::LowScore(scoring) => -get_scoring_score(tiles, *scoring, game_meta),
+    }
+}
+
+/// Returns the score of a game ending with the given tiles under a scoring
+fn get_scoring_score(tiles: &Tiles, scoring: Scoring, game_meta: &GameMeta) -> Float {
+    match scoring {
+        Scoring::PipSum => get_score(tiles, game_meta) as Float,
+        Scoring::Digits => tiles.iter().fold(0., |score, tile| {
+            let digit_cnt = tile.checked_ilog10().unwrap_or(0) + 1;
+            score * (10 as Float).powi(digit_cnt as i32) + *tile as Float
+        }),
+        Scoring::TileCount => tiles.len() as Float,
+    }
+}
+
+/// Returns every combination of the given tiles that may be flipped for a roll, each sorted.
+/// Leaves out the include highest limit, which depends on the game state the tiles are flipped from.
+fn get_flips(tiles: &Tiles, roll: &Roll, game_meta: &GameMeta) -> Vec<Tiles> {
+    let mut flips = Vec::new();
+    if game_meta.removal_rule != RemovalRule::IndividualOnly {
+        let total = get_roll_total(roll, game_meta.combiner, game_meta.doubles_rule);
+        flips.extend(get_target_flips(tiles, &total, game_meta));
+    }
+    if game_meta.removal_rule != RemovalRule::SumOnly {
+        flips.extend(get_individual_flips(tiles, roll));
+    }
+    for flip in &mut flips {
+        flip.sort_unstable();
+    }
+    flips.sort_unstable();
+    flips.dedup();
+    flips.retain(|flip| get_flip_allowed(flip, game_meta));
+    flips
+}
+
+/// Returns whether a roll that can't shut any tiles may re-open the given shut tile under open and shut:
+/// the tile matching the dice total, or under individual removal the tile matching every die.
+/// Re-opening always moves a single tile, so the removal limits don't apply to it.
+fn get_reopen_allowed(tile: Uns, roll: &Roll, game_meta: &GameMeta) -> bool {
+    let sum_match = game_meta.removal_rule != RemovalRule::IndividualOnly
+        && tile == get_roll_total(roll, game_meta.combiner, game_meta.doubles_rule);
+    let individual_match =
+        game_meta.removal_rule != RemovalRule::SumOnly && roll.iter().all(|die| *die == tile);
+    sum_match || individual_match
+}
+
+/// Returns whether a sorted combination of tiles may be flipped under the removal limits, besides include highest
+fn get_flip_allowed(flip: &Tiles, game_meta: &GameMeta) -> bool {
+    let limits = &game_meta.removal_limits;
+    if limits.exact_count > 0 && flip.len() != limits.exact_count as usize {
+        return false;
+    }
+    if limits.adjacent
+        && !flip.windows(2).all(|pair| {
+            !game_meta
+                .tiles
+                .iter()
+                .any(|tile| pair[0] < *tile && *tile < pair[1])
+        })
+    {
+        return false;
+    }
+    if let Some(one_above) = limits.one_above {
+        if flip.iter().filter(|tile| **tile > one_above).count() > 1 {
+            return false;
+        }
+    }
+    true
+}
+
+/// Returns every combination of the given tiles summing to a target, out of the Tile Removal Possibilities
+fn get_target_flips(tiles: &Tiles, target: &Uns, game_meta: &GameMeta) -> Vec<Tiles> {
+    match game_meta.trphm.get(target) {
+        Some(trps) => trps
+            .iter()
+            .filter(|trp| get_removed_tiles(tiles, trp).is_some())
+            .cloned()
+            .collect(),
+        None => Vec::new(),
+    }
+}
+
+/// Returns the tiles matching the dice, one tile per die, empty if any of them is shut.
+/// Dice showing the same face flip the same tile.
+fn get_individual_flips(tiles: &Tiles, dice: &[Uns]) -> Vec<Tiles> {
+    let mut flip = dice.to_vec();
+    flip.sort_unstable();
+    flip.dedup();
+    match get_removed_tiles(tiles, &flip) {
+        Some(_) => vec![flip],
+        None => Vec::new(),
+    }
+}
+
+/// Returns a vec of tile possibilities for the next turn given a roll
+fn get_next_legal_states_roll(tiles: &Tiles, roll: &Roll, game_meta: &GameMeta) -> Vec<Tiles> {
+    game_meta.move_gen.get_next_states(tiles, roll, game_meta)
+}
+
+/// Returns a HashMap of all next legal states where key: roll, value: vec of tile possibilities for the next turn
+fn get_next_legal_states_all(tiles: &Tiles, game_meta: &GameMeta) -> HashMap<Roll, Vec<Tiles>> {
+    let mut hm = HashMap::new();
+    if tiles.is_empty() {
+        return hm;
+    }
+    for roll in &game_meta.rolls {
+        let legal_states = get_next_legal_states_roll(tiles, roll, game_meta);
+        if !legal_states.is_empty() {
+            hm.insert(roll.clone(), legal_states);
+        }
+    }
+    hm
+}
+
+/// TODO
+fn get_all_stats_from_states(
+    states: &Vec<Tiles>,
+    game_meta: &GameMeta,
+    game_db: &mut HashMap<Tiles, Float>,
+) -> Vec<(Tiles, Float)> {
+    let mut res = Vec::new();
+    for state in states {
+        let state = state.clone();
+        let stats = depth_solve(state.clone(), game_meta, game_db);
+        res.push((state, stats));
+    }
+    res
+}
+
+/// TODO
+fn get_all_stats_from_hm(
+    state_hm: &HashMap<Roll, Vec<Tiles>>,
+    game_meta: &GameMeta,
+    game_db: &mut HashMap<Tiles, Float>,
+) -> HashMap<Roll, Vec<(Tiles, Float)>> {
+    let mut hm = HashMap::new();
+    for (roll, game_states) in state_hm {
+        let games = get_all_stats_from_states(&game_states.clone(), game_meta, game_db);
+        hm.insert(roll.clone(), games);
+    }
+    hm
+}
+
+/// Returns a bool of the legality of rolling a single die
+fn get_single_legality(tiles: &Tiles, max_die: &Uns) -> bool {
+    !tiles.is_empty() && tiles.iter().max().unwrap() <= max_die
+}
+
+/// Returns the maximum value within a Vec<Uns>, used to find max die value
+fn get_max(vals: &[Uns]) -> Uns {
+    *vals.iter().max().unwrap()
+}
+
+/// If the given tiles can be removed, returns the new tiles, otherwise returns None
+fn get_removed_tiles(tiles: &Tiles, trp: &Tiles) -> Option<Tiles> {
+    let mut new_tiles = tiles.clone();
+    for &tile in trp {
+        if new_tiles.contains(&tile) {
+            new_tiles.remove(new_tiles.iter().position(|&x| x == tile).unwrap());
+        } else {
+            return None;
+        }
+    }
+    Some(new_tiles)
+}
+
+/// Returns the tiles flipped to get from one board to the next, highest first
+fn get_flipped_tiles(tiles: &Tiles, next_tiles: &Tiles) -> Tiles {
+    let mut flipped = tiles.clone();
+    for tile in next_tiles {
+        if let Some(pos) = flipped.iter().position(|x| x == tile) {
+            flipped.remove(pos);
+        }
+    }
+    flipped.sort_unstable_by(|a, b| b.cmp(a));
+    flipped
+}
+
+///// SETUP FUNCTIONS /////
+
+/// Returns tiles as a readable String, ex: "1 4 7"
+pub(crate) fn get_tiles_string(tiles: &[Uns]) -> String {
+    tiles
+        .iter()
+        .map(|tile| tile.to_string())
+        .collect::<Vec<String>>()
+        .join(" ")
+}
+
+/// Returns a sorted vector from an unsorted vector
+fn get_srt<T: Copy + Ord>(a: &[T]) -> Vec<T> {
+    let mut b = a.to_vec();
+    b.sort_unstable();
+    b
+}
+
+/// Returns the sorted deduplicated keys of two HashMaps
+fn get_srt_dedup_keys<T, U>(hm1: &HashMap<Roll, T>, hm2: &HashMap<Roll, U>) -> Vec<Roll> {
+    let mut x = hm1.keys().cloned().collect::<Vec<Roll>>();
+    x.append(&mut hm2.keys().cloned().collect::<Vec<Roll>>());
+    x.sort_unstable();
+    x.dedup();
+    x
+}
+
+/// Returns the sorted deduplicated totals of a Vec of rolls
+fn get_srt_dedup_totals(rolls: &[Roll], combiner: Combiner, doubles_rule: DoublesRule) -> Vec<Uns> {
+    let mut x = rolls
+        .iter()
+        .map(|roll| get_roll_total(roll, combiner, doubles_rule))
+        .collect::<Vec<Uns>>();
+    x = get_srt(&x);
+    x.dedup();
+    x
+}
+
+/// Returns a HashMap of all combinations of tiles to remove from a given roll
+/// key: roll, value: Vec<Tiles>, where Tiles is the combination of tiles to remove
+pub fn get_tile_removal_possibilities(
+    tiles: &Tiles,
+    possible_rolls: &Vec<Uns>,
+    removal_max: &Uns,
+) -> HashMap<Uns, Vec<Tiles>> {
+    let mut trp: HashMap<Uns, Vec<Tiles>> = HashMap::new();
+    for roll in possible_rolls {
+        if roll > &0 {
+            let removals: Vec<Tiles> = r_tile_removal(tiles, roll, removal_max);
+            trp.insert(*roll, removals);
+        } else {
+            trp.insert(*roll, vec![vec![0]]);
+        }
+    }
+    trp
+}
+
+/// Recursive function that returns all possible combinations of tiles to remove from a given roll
+fn r_tile_removal(tiles: &[Uns], targ: &Uns, removal_max: &Uns) -> Vec<Tiles> {
+    let mut removals: Vec<Tiles> = Vec::new();
+    if targ == &0 {
+        removals.push(Vec::new());
+        return removals;
+    }
+    if removal_max == &1 {
+        for tile in tiles {
+            if tile == targ {
+                removals.push(vec![*tile]);
+            }
+        }
+    } else {
+        for tile in tiles {
+            if tile <= targ {
+                let start = tiles.iter().position(|&x| x == *tile).unwrap();
+                let new_tiles = &tiles[start + 1..];
+                let new_removal_max = if removal_max > &1 { removal_max - 1 } else { 0 };
+                let new_removals = r_tile_removal(new_tiles, &(targ - tile), &new_removal_max);
+                for mut removal in new_removals {
+                    removal.push(*tile);
+                    removals.push(removal);
+                }
+            }
+        }
+    }
+    removals
+}
+
+/// Returns a Hashmap of all possible rolls and their probabilities given some die_vals and die_cnt.
+/// Rolls keep the face of each die if keep_dice is set, otherwise they're just the total the dice combine into.
+/// Builds the distribution one die at a time, so it grows with the distinct rolls instead of faces^dice.
+/// Faces listed more than once are weighted by how often they're listed.
+pub fn get_roll_probs(
+    die_vals: &[Uns],
+    die_cnt: Uns,
+    keep_dice: bool,
+    combiner: Combiner,
+) -> HashMap<Roll, Float> {
+    let mut face_probs: HashMap<Uns, Float> = HashMap::new();
+    for face in die_vals {
+        *face_probs.entry(*face).or_insert(0.) += 1. / die_vals.len() as Float;
+    }
+
+    // key: the sorted faces rolled so far, or just enough of them to get their total
+    let mut partial_probs: HashMap<Roll, Float> = HashMap::from([(Vec::new(), 1.)]);
+    for _ in 0..die_cnt {
+        let mut next_probs = HashMap::new();
+        for (partial, partial_prob) in &partial_probs {
+            for (face, face_prob) in &face_probs {
+                let next_partial = if keep_dice {
+                    let mut dice = partial.clone();
+                    dice.insert(dice.partition_point(|die| die <= face), *face);
+                    dice
+                } else {
+                    combiner.get_partial(partial, *face)
+                };
+                *next_probs.entry(next_partial).or_insert(0.) += partial_prob * face_prob;
+            }
+        }
+        partial_probs = next_probs;
+    }
+
+    if keep_dice {
+        return partial_probs;
+    }
+    let mut roll_probs = HashMap::new();
+    for (partial, prob) in partial_probs {
+        *roll_probs
+            .entry(vec![combiner.get_total(&partial)])
+            .or_insert(0.) += prob;
+    }
+    roll_probs
+}
+
+/// Returns the chance of each roll of a single die and of every die under the given rules
+fn get_roll_probs_both(init_data: &InitData) -> (HashMap<Roll, Float>, HashMap<Roll, Float>) {
+    // todo probably can optimize with this sorted
+    // todo eventually make this where the num dice rolled is totally dynamic
+    let keep_dice = get_keeps_dice(init_data.removal_rule, init_data.doubles_rule);
+    let roll_probs_single = get_roll_probs(&init_data.die_vals, 1, keep_dice, init_data.combiner);
+    let roll_probs_multi = get_roll_probs(
+        &init_data.die_vals,
+        init_data.die_cnt,
+        keep_dice,
+        init_data.combiner,
+    );
+    (roll_probs_single, roll_probs_multi)
+}
+
+/// Returns the game meta for the given rules, or why their moves can't be generated
+pub fn get_game_meta(init_data: &InitData) -> Result<GameMeta, String> {
+    let die_max = get_max(&init_data.die_vals);
+
+    let (roll_probs_single, roll_probs_multi) = get_roll_probs_both(init_data);
+
+    let rolls = get_srt_dedup_keys(&roll_probs_multi, &roll_probs_single);
+    // single die rolls total their face, so these also cover the tiles individual dice flip
+    let roll_possib = get_srt_dedup_totals(&rolls, init_data.combiner, init_data.doubles_rule);
+
+    let trphm =
+        get_tile_removal_possibilities(&init_data.start_tiles, &roll_possib, &init_data.max_remove);
+
+    let mut game_meta = GameMeta {
+        die_max,
+        die_cnt: init_data.die_cnt,
+        trphm,
+        roll_probs_single,
+        roll_probs_multi,
+        rolls,
+        tiles: get_srt(&init_data.start_tiles),
+        tile_points: init_data.tile_points.clone(),
+        box_mode: init_data.box_mode,
+        combiner: init_data.combiner,
+        removal_rule: init_data.removal_rule,
+        removal_limits: init_data.removal_limits.clone(),
+        doubles_rule: init_data.doubles_rule,
+        stop_early: init_data.stop_early,
+        mulligans: init_data.mulligans,
+        mulligan_db: None,
+        objective: Objective::WinChance,
+        match_limit: init_data.match_limit,
+        move_gen: Arc::default(),
+    };
+    game_meta.move_gen = Arc::new(move_gen::MoveGen::new(&game_meta)?);
+    Ok(game_meta)
+}
@@ -1,64 +1,34 @@
-use rayon::prelude::*;
 use rustop::opts;
+use shut_the_box_rust::rules::Rules;
+use shut_the_box_rust::utility;
+use shut_the_box_rust::*;
 use std::collections::HashMap;
+use std::net::SocketAddr;
 
-/// The unsigned int type used for all non-usize int calculations
-type Uns = u16;
-/// The float type used for all float calculations
-type Float = f64;
-/// Helper type alias for a vector of tile values, for readability
-type Tiles = Vec<Uns>;
-
-/// The parent of a given game containing all data from solving the game
-#[derive(Debug)]
-struct Trunk {
-    game_meta: GameMeta,
-
-    game_db: HashMap<Tiles, Float>, // contains all possible child game states
+/// What the program was asked to do
+enum Command {
+    /// Solve the game and report on it
+    Solve,
+    /// Solve the game and report on a single game state
+    Query(Tiles),
+    /// Answer JSON requests about any rules on a local port
+    Serve(u16),
 }
 
-// TODO allow multiple algos?
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
-enum Algorithm {
-    All,
-    Naive,
-    Depth,
-    Parallel,
-    Default,
-}
-
-/// Minimal necessary data to calculate a given game. Shared between all game states of a game.
-#[derive(Debug, Clone)]
-struct GameMeta {
-    /// The maximum value of the die
-    die_max: Uns,
-    /// The starting tiles
-    tiles: Tiles,
-
-    /// Tile Removal Possibilities Hash Map, key: roll, value: Vec of tile combinations to remove.
-    /// This is calculated given the starting tiles, the die sides, and the max number of tiles to remove
-    trphm: HashMap<Uns, Vec<Tiles>>,
-    /// Perfect play win chance if a single die is rolled (0 if not legal)
-    roll_probs_single: HashMap<Uns, Float>,
-    /// Perfect play win chance if multiple dice are rolled (always legal)
-    roll_probs_multi: HashMap<Uns, Float>,
-
-    /// The algorithm to use for solving
-    algorithm: Algorithm,
-}
+/// The port the JSON API listens on when none is given
+const DEFAULT_PORT: u16 = 8080;
 
-/// Data extracted from program args (or lack thereof)
-struct InitData {
-    /// The sides of the given die
-    die_vals: Vec<Uns>,
-    /// The number of dice
-    die_cnt: Uns,
-    /// The starting tiles (trunk)
-    start_tiles: Tiles,
-    /// The maximum number of tiles to remove on a given turn
-    max_remove: Uns,
-    /// Run all algos
-    algorithm: Algorithm,
+fn main() {
+    let (init_data, command) = parse_args();
+    let result = match command {
+        Command::Solve => run(init_data),
+        Command::Query(tiles) => run_query(&init_data, &tiles),
+        Command::Serve(port) => run_serve(&init_data, port),
+    };
+    if let Err(err) = result {
+        eprintln!("{}", err);
+        std::process::exit(1);
+    }
 }
 
 /// Solves a given game.
@@ -69,442 +39,242 @@ struct InitData {
 /// General Improvements
 ///     Allow to roll any number of dice, not just multi and single
 /// Remove #[derive(Debug)] from structs
-fn main() {
-    // TODO make these command line args
-
+fn run(init_data: InitData) -> Result<(), String> {
     let start = std::time::Instant::now();
 
-    let game_meta = get_game_meta();
+    let game_meta = try_get_checked_game_meta(&init_data)?;
+    let (game_meta, mulligan_win_chances) =
+        get_mulligan_game_meta_from(game_meta, &init_data.get_open_tiles());
+    for (mulligans, win_chance) in mulligan_win_chances.iter().enumerate() {
+        println!(
+            "Win chance with {} mulligans: {:.2}%",
+            mulligans,
+            win_chance * 100.0
+        );
+    }
     println!("Done with setup, solving game states...");
     println!("Game initialized in {:.3}s", start.elapsed().as_secs_f64());
 
+    if init_data.open_tiles.is_some() || init_data.pending_roll.is_some() {
+        return run_mid_game(&init_data, game_meta);
+    }
 
-    let algorithm = if game_meta.algorithm == Algorithm::Default {
-        Algorithm::Parallel
+    let tiles = init_data.get_open_tiles();
+    let has_cycles = init_data.box_mode.get_has_cycles();
+    let algorithm = if init_data.algorithm == Algorithm::Default {
+        if has_cycles {
+            Algorithm::Value
+        } else if cfg!(feature = "parallel") {
+            Algorithm::Parallel
+        } else {
+            Algorithm::Depth
+        }
+    } else {
+        init_data.algorithm
+    };
+    #[cfg(not(feature = "parallel"))]
+    let algorithm = if algorithm == Algorithm::Parallel {
+        println!("Built without the parallel feature, solving with depth algorithm instead...");
+        Algorithm::Depth
     } else {
-        game_meta.algorithm
+        algorithm
+    };
+    if has_cycles && ![Algorithm::All, Algorithm::Value].contains(&algorithm) {
+        println!(
+            "Game states can repeat with these rules, solving with value iteration instead..."
+        );
+    }
+    let algorithm = if has_cycles && algorithm != Algorithm::All {
+        Algorithm::Value
+    } else {
+        algorithm
     };
 
-    if algorithm == Algorithm::All || algorithm == Algorithm::Naive {
+    if has_cycles && algorithm == Algorithm::All {
+        println!("Game states can repeat with these rules, skipping recursive algorithms...\n");
+    }
+
+    let rolls_again = init_data.doubles_rule.get_rolls_again();
+    if rolls_again && algorithm == Algorithm::Naive {
+        println!("The naive algorithm can't roll again from the same game state, skipping\n");
+    }
+    if !has_cycles && !rolls_again && (algorithm == Algorithm::All || algorithm == Algorithm::Naive)
+    {
         println!("Solving with naive algorithm...");
         let start = std::time::Instant::now();
-        let (naive_prob, game_count) = naive_solve(game_meta.tiles.clone(), &game_meta);
+        let (naive_prob, game_count) = naive_solve(tiles.clone(), &game_meta);
         println!("num of game entries: {}", game_count);
         println!("Win chance: {:.2}%", naive_prob * 100.0);
         let duration = start.elapsed().as_secs_f64();
         println!("Time elapsed in naive_solve() is: {:.3}s\n", duration);
     }
-    if algorithm == Algorithm::All || algorithm == Algorithm::Depth {
+    if !has_cycles && (algorithm == Algorithm::All || algorithm == Algorithm::Depth) {
         println!("Solving with depth algorithm...");
         let start = std::time::Instant::now();
         let mut depth_db = HashMap::new();
-        depth_solve(game_meta.tiles.clone(), &game_meta, &mut depth_db);
+        depth_solve(tiles.clone(), &game_meta, &mut depth_db);
         println!("num of game entries: {}", depth_db.len());
-        println!(
-            "Win chance: {:.2}%",
-            depth_db.get(&game_meta.tiles).unwrap() * 100.0
-        );
+        println!("Win chance: {:.2}%", depth_db.get(&tiles).unwrap() * 100.0);
         let duration = start.elapsed().as_secs_f64();
         println!("Time elapsed in depth_solve() is: {:.3}s\n", duration);
     }
-    if algorithm == Algorithm::All || algorithm == Algorithm::Parallel {
+    #[cfg(feature = "parallel")]
+    if !has_cycles && (algorithm == Algorithm::All || algorithm == Algorithm::Parallel) {
         println!("Solving with parallel algorithm...");
         let start = std::time::Instant::now();
-        let par_db = par_solve(game_meta.tiles.clone(), game_meta.clone());
+        let par_db = par_solve(tiles.clone(), game_meta.clone());
         println!("num of game entries: {}", par_db.len());
-        println!(
-            "Win chance: {:.2}%",
-            par_db.get(&game_meta.tiles).unwrap() * 100.0
-        );
+        println!("Win chance: {:.2}%", par_db.get(&tiles).unwrap() * 100.0);
         let duration = start.elapsed().as_secs_f64();
         println!("Time elapsed in par_solve() is: {:.3}s\n", duration);
     }
-
-    let duration = start.elapsed().as_secs_f64();
-    println!("Total time elapsed is: {:.3}s\n", duration);
-}
-
-/// Gets all combinations of remaining tiles, ordered by increasing number of tiles remaining
-/// TODO probably doesn't work when there are gaps in number of tiles remaining
-fn get_tile_combos(tiles: &Tiles) -> Vec<Vec<Tiles>> {
-    let mut game_states = Vec::new();
-    for i in 1..tiles.len() {
-        game_states.push(get_game_states_by_tiles_remaining(tiles, &Vec::new(), i));
-    }
-    game_states
-}
-
-/// Gets all possible game states with a given start condition and a number of tiles remaining
-fn get_game_states_by_tiles_remaining(
-    remaining_tiles: &Tiles,
-    curr_tiles: &Tiles,
-    num_tiles: usize,
-) -> Vec<Tiles> {
-    // TODO async?
-    if num_tiles == curr_tiles.len() {
-        return vec![curr_tiles.clone()];
-    }
-    let mut sol = Vec::new();
-    for i in 0..remaining_tiles.len() {
-        if curr_tiles.len() > 0 && remaining_tiles[i] < curr_tiles[curr_tiles.len() - 1] {
-            continue;
-        }
-        let mut curr_tiles = curr_tiles.clone();
-        let mut remaining_tiles = remaining_tiles.clone();
-        curr_tiles.push(remaining_tiles.remove(i));
-        let sols = get_game_states_by_tiles_remaining(&remaining_tiles, &curr_tiles, num_tiles);
-        sol.extend(sols);
+    if algorithm == Algorithm::All || algorithm == Algorithm::Value {
+        println!("Solving with value iteration algorithm...");
+        let start = std::time::Instant::now();
+        let value_db = value_solve(&game_meta);
+        println!("num of game entries: {}", value_db.len());
+        println!("Win chance: {:.2}%", value_db.get(&tiles).unwrap() * 100.0);
+        let duration = start.elapsed().as_secs_f64();
+        println!("Time elapsed in value_solve() is: {:.3}s\n", duration);
     }
-    sol
-}
 
-/// Solves a given game in parallel
-fn par_solve(tiles: Tiles, game_meta: GameMeta) -> HashMap<Tiles, Float> {
-    let mut result = HashMap::new();
-
-    print!("about to get tile combos... ");
-    let t_combos = get_tile_combos(&tiles);
-    println!("DONE");
-
-    for t_combo in t_combos {
-        let vec: Vec<Tiles> = t_combo;
-        result = result.clone();
-        let par_iter = vec
-            .par_iter()
-            // .filter_map(|value| value.as_ref().ok())
-            .map(|chunk| {
-                let mut res = result.clone();
-                depth_solve(chunk.to_vec(), &game_meta, &mut res);
-                res
-            })
-            .reduce(
-                || HashMap::new(),
-                |m1, m2| {
-                    let mut res = m1;
-                    res.extend(m2);
-                    res
-                },
-            );
-        println!("par_iter len: {:?}", par_iter.len());
-        result.extend(par_iter);
+    if init_data.doubles_rule != DoublesRule::NoBonus {
+        println!("Comparing against the same rules without the doubles bonus...");
+        let trunk = get_trunk(game_meta.clone());
+        // without the bonus no roll flips a tile it couldn't flip before, so these rules are fine too
+        let plain_game_meta = get_game_meta(&InitData {
+            doubles_rule: DoublesRule::NoBonus,
+            ..init_data.clone()
+        })?;
+        let plain_trunk = get_trunk(get_mulligan_game_meta(plain_game_meta));
+        let win_chance = trunk.game_db.get(&tiles).unwrap();
+        let plain_win_chance = plain_trunk.game_db.get(&tiles).unwrap();
+        println!("Win chance with doubles bonus: {:.2}%", win_chance * 100.0);
+        println!(
+            "Win chance without doubles bonus: {:.2}%",
+            plain_win_chance * 100.0
+        );
+        println!(
+            "The doubles bonus adds {:.2} percentage points\n",
+            (win_chance - plain_win_chance) * 100.0
+        );
     }
-    depth_solve(tiles, &game_meta, &mut result);
-    result
-}
-
-/// Returns a readable String of the given GameMeta
-#[allow(dead_code)]
-fn get_readable_game_meta(game_meta: &GameMeta) -> String {
-    let mut out = String::new();
-    out.push_str(&format!("    die_max: {}\n", game_meta.die_max));
-    out.push_str(&format!("    start_tiles: {:?}\n", game_meta.tiles));
-    out.push_str(&format!("    trphm: {:?}\n", game_meta.trphm));
-    out.push_str(&format!(
-        "    roll_probs_single: {:?}\n",
-        game_meta.roll_probs_single
-    ));
-    out.push_str(&format!(
-        "    roll_probs_multi: {:?}\n",
-        game_meta.roll_probs_multi
-    ));
-    out
-}
-
-/// Returns a readable String of the given Trunk
-#[allow(dead_code)]
-fn get_readable_trunk_string(trunk: &Trunk) -> String {
-    let mut s = String::new();
-    s.push_str("  Game Meta:\n");
-    s.push_str(&get_readable_game_meta(&trunk.game_meta));
-    s.push_str("\nWIN CHANCE:\n");
-    s.push_str(&format!(
-        "  {:?}",
-        trunk.game_db.get(&trunk.game_meta.tiles).unwrap()
-    ));
-    s.push_str("\n");
-    s
-}
-
-/// Recursively and naively solves a given game through a breadth-first traversal
-fn naive_solve(tiles: Tiles, game_meta: &GameMeta) -> (Float, u64) {
-    let (win_single, game_calc_cnt_single) = naive_solve_single(tiles.clone(), game_meta);
-    let (win_multi, game_calc_cnt_multi) = naive_solve_multi(tiles.clone(), game_meta);
-    (win_single.max(win_multi), game_calc_cnt_single + game_calc_cnt_multi)
-}
 
-fn naive_solve_single(tiles: Tiles, game_meta: &GameMeta) -> (Float, u64) {
-    if tiles.len() == 0 {
-        return (1., 0);
-    }
-    let mut prob = 0.;
-    let roll_probs = &game_meta.roll_probs_single;
-    let trphm = &game_meta.trphm;
-    let mut game_count = 1;
-    for (roll, roll_prob) in roll_probs {
-        let trps = trphm.get(roll).unwrap();
-        let mut rolls = Vec::new();
-        for trp in trps {
-            let new_tiles = get_removed_tiles(&tiles, trp);
-            match new_tiles {
-                Some(new_tiles) => {
-                    let curr_prob = roll_prob;
-                    let (win_chance, new_game_count) = naive_solve(new_tiles, game_meta);
-                    game_count += new_game_count;
-                    rolls.push(curr_prob * win_chance);
-                }
-                None => {}
-            }
-        }
-        if rolls.len() > 0 {
-            prob += rolls.iter().cloned().fold(0. / 0., f64::max);
+    if has_cycles && (init_data.cheat_sheet.is_some() || init_data.heuristics) {
+        println!(
+            "Cheat sheets and heuristics need rules where game states can't repeat, skipping\n"
+        );
+    } else if let Some(path) = &init_data.cheat_sheet {
+        println!("Writing cheat sheet...");
+        let trunk = get_trunk(game_meta.clone());
+        match cheat_sheet::write_cheat_sheet(&trunk, path, init_data.cheat_rows) {
+            Ok(()) => println!("Cheat sheet written to {}\n", path),
+            Err(err) => eprintln!("Could not write cheat sheet to {}: {}\n", path, err),
         }
     }
-    (prob, game_count)
-}
 
-fn naive_solve_multi(tiles: Tiles, game_meta: &GameMeta) -> (Float, u64) {
-    if tiles.len() == 0 {
-        return (1., 0);
-    }
-    let mut prob = 0.;
-    let roll_probs = &game_meta.roll_probs_multi;
-    let trphm = &game_meta.trphm;
-    let mut game_count = 1;
-    for (roll, roll_prob) in roll_probs {
-        let trps = trphm.get(roll).unwrap();
-        let mut rolls = Vec::new();
-        for trp in trps {
-            let new_tiles = get_removed_tiles(&tiles, trp);
-            match new_tiles {
-                Some(new_tiles) => {
-                    let curr_prob = roll_prob;
-                    let (win_chance, new_game_count) = naive_solve(new_tiles, game_meta);
-                    game_count += new_game_count;
-                    rolls.push(curr_prob * win_chance);
-                }
-                None => {}
-            }
-        }
-        if rolls.len() > 0 {
-            prob += rolls.iter().cloned().fold(0. / 0., f64::max);
-        }
+    if init_data.heuristics && !has_cycles {
+        println!("Comparing heuristics against perfect play...");
+        let trunk = get_trunk(game_meta.clone());
+        println!("{}", heuristics::get_readable_heuristics_string(&trunk, 10));
     }
-    (prob, game_count)
-}
 
-/// Recursively solves a given game through a depth-first traversal
-fn depth_solve(tiles: Tiles, game_meta: &GameMeta, game_db: &mut HashMap<Tiles, Float>) -> Float {
-    let tiles = tiles.clone();
-    let existing_game = game_db.get(&tiles);
-    match existing_game {
-        Some(existing_game) => {
-            return existing_game.clone();
-        }
-        None => {
-            if tiles.len() == 0 {
-                let win_chance = 1.;
-                game_db.insert(tiles, win_chance);
-                return win_chance;
-            }
-            let all_next_legal_states_hm = get_next_legal_states_all(&tiles, &game_meta.trphm);
-            let solved_next_legal_states_hm =
-                get_all_stats_from_hm(&all_next_legal_states_hm, game_meta, game_db);
-
-            let mut best_states_single_hm = HashMap::new();
-            let mut best_states_multi_hm = HashMap::new();
-            let mut win_chance_single = 0.;
-            let mut win_chance_multi = 0.;
-            for (roll, state_stat) in solved_next_legal_states_hm {
-                let mut curr_best_win_chance = 0.;
-                let mut curr_best_state: Tiles = vec![];
-                for (state, win_chance) in state_stat {
-                    if win_chance > curr_best_win_chance {
-                        curr_best_win_chance = win_chance;
-                        curr_best_state = state;
-                    }
-                }
-                if get_single_legality(&tiles, &game_meta.die_max) {
-                    let single_chance = game_meta.roll_probs_single.get(&roll);
-                    match single_chance {
-                        Some(single_chance) => {
-                            best_states_single_hm.insert(roll, curr_best_state.clone());
-                            win_chance_single += curr_best_win_chance * single_chance;
-                        }
-                        None => {}
-                    }
-                }
-                let multi_chance = game_meta.roll_probs_multi.get(&roll);
-                match multi_chance {
-                    Some(multi_chance) => {
-                        best_states_multi_hm.insert(roll, curr_best_state.clone());
-                        win_chance_multi += curr_best_win_chance * multi_chance;
-                    }
-                    None => {}
-                }
+    if let Some(utility) = &init_data.utility {
+        if has_cycles {
+            println!("Utility goals need rules where game states can't repeat, skipping\n");
+        } else {
+            println!("Solving for a goal besides shutting the box...");
+            match utility::get_readable_utility_string(&game_meta, utility, 10) {
+                Ok(report) => println!("{}", report),
+                Err(err) => eprintln!("{}\n", err),
             }
-            let win_chance = win_chance_single.max(win_chance_multi);
-            game_db.insert(tiles, win_chance);
-            win_chance
         }
     }
-}
 
-/// Returns a vec of tile possibilities for the next turn given a Tile Removal Possibilities for a given roll.
-fn get_next_legal_states_roll(tiles: &Tiles, trps: &Vec<Tiles>) -> Vec<Tiles> {
-    let mut legal_states = Vec::new();
-    for trp in trps {
-        let new_tiles = get_removed_tiles(tiles, trp);
-        match new_tiles {
-            Some(new_tiles) => {
-                legal_states.push(new_tiles);
-            }
-            None => {}
+    if let Some(scoring) = init_data.scoring {
+        if has_cycles {
+            println!("Scoring needs rules where game states can't repeat, skipping\n");
+        } else {
+            println!("Solving for the lowest expected score...");
+            println!(
+                "{}",
+                utility::get_readable_scoring_string(&game_meta, scoring, 10)
+            );
         }
     }
-    legal_states
-}
 
-/// Returns a HashMap of all next legal states where key: roll, value: vec of tile possibilities for the next turn
-fn get_next_legal_states_all(
-    tiles: &Tiles,
-    trphm: &HashMap<Uns, Vec<Tiles>>,
-) -> HashMap<Uns, Vec<Tiles>> {
-    let mut hm = HashMap::new();
-    if tiles.len() == 0 {
-        return hm;
-    }
-    for (roll, trps) in trphm {
-        let legal_states = get_next_legal_states_roll(tiles, trps);
-        if legal_states.len() > 0 {
-            hm.insert(*roll, legal_states);
+    if init_data.stop_early {
+        if has_cycles {
+            println!("Stopping early needs rules where game states can't repeat, skipping\n");
+        } else {
+            println!("Solving when to stop early...");
+            let scoring = init_data.scoring.unwrap_or(Scoring::PipSum);
+            println!(
+                "{}",
+                stopping::get_readable_stopping_string(&game_meta, scoring)
+            );
         }
     }
-    hm
-}
-
-/// TODO
-fn get_all_stats_from_states(
-    states: &Vec<Tiles>,
-    game_meta: &GameMeta,
-    game_db: &mut HashMap<Tiles, Float>,
-) -> Vec<(Tiles, Float)> {
-    let mut res = Vec::new();
-    for state in states {
-        let state = state.clone();
-        let stats = depth_solve(state.clone(), game_meta, game_db);
-        res.push((state, stats));
-    }
-    res
-}
-
-/// TODO
-fn get_all_stats_from_hm(
-    state_hm: &HashMap<Uns, Vec<Tiles>>,
-    game_meta: &GameMeta,
-    game_db: &mut HashMap<Tiles, Float>,
-) -> HashMap<Uns, Vec<(Tiles, Float)>> {
-    let mut hm = HashMap::new();
-    for (roll, game_states) in state_hm {
-        let games = get_all_stats_from_states(&game_states.clone(), game_meta, game_db);
-        hm.insert(*roll, games);
-    }
-    hm
-}
 
-/// Returns a bool of the legality of rolling a single die
-fn get_single_legality(tiles: &Tiles, max_die: &Uns) -> bool {
-    tiles.len() > 0 && tiles.iter().max().unwrap() <= max_die
-}
-
-/// Returns the maximum value within a Vec<Uns>, used to find max die value
-fn get_max(vals: &[Uns]) -> Uns {
-    vals.iter().max().unwrap().clone()
-}
-
-/// If the given tiles can be removed, returns the new tiles, otherwise returns None
-fn get_removed_tiles(tiles: &Tiles, trp: &Tiles) -> Option<Tiles> {
-    let mut new_tiles = tiles.clone();
-    for &tile in trp {
-        if new_tiles.contains(&tile) {
-            new_tiles.remove(new_tiles.iter().position(|&x| x == tile).unwrap());
+    if init_data.match_limit > 0 {
+        if has_cycles {
+            println!("Matches need rules where game states can't repeat, skipping\n");
         } else {
-            return None;
+            println!("Solving a match to {}...", init_data.match_limit);
+            println!("{}", match_play::get_readable_match_string(&game_meta));
         }
     }
-    Some(new_tiles)
-}
 
-///// SETUP FUNCTIONS /////
-
-/// Returns a sorted vector from an unsorted vector
-fn get_srt<T: Copy + Ord>(a: &[T]) -> Vec<T> {
-    let mut b = a.to_vec();
-    b.sort_unstable();
-    b
+    let duration = start.elapsed().as_secs_f64();
+    println!("Total time elapsed is: {:.3}s\n", duration);
+    Ok(())
 }
 
-/// Returns a sorted deduplicated vector from an unsorted vector
-fn get_srt_dedup_keys<T, U>(hm1: &HashMap<Uns, T>, hm2: &HashMap<Uns, U>) -> Vec<Uns> {
-    let mut x = hm1.keys().map(|&x| x).collect::<Vec<Uns>>();
-    x.append(&mut hm2.keys().map(|&x| x).collect::<Vec<Uns>>());
-    x = get_srt(&x);
-    x.dedup();
-    x
+/// Solves a game in progress from its open tiles and prints what perfect play knows about them,
+/// and the best move for the pending roll if there is one, or returns why they can't be queried
+fn run_mid_game(init_data: &InitData, game_meta: GameMeta) -> Result<(), String> {
+    let start = std::time::Instant::now();
+    let open_tiles = init_data.get_open_tiles();
+    let mut trunk = get_trunk_from(game_meta, &open_tiles);
+    println!(
+        "Solved {} game states from the open tiles in {:.3}s\n",
+        trunk.game_db.len(),
+        start.elapsed().as_secs_f64()
+    );
+    let state_query = query::query_state(&mut trunk, &open_tiles)
+        .map_err(|err| format!("Can't query the open tiles: {}", err))?;
+    print!("{}", query::get_readable_query_string(&state_query));
+    if let Some(dice) = &init_data.pending_roll {
+        let roll_choice = query::query_roll(&mut trunk, &open_tiles, dice)
+            .map_err(|err| format!("Can't query the roll: {}", err))?;
+        print!("\n{}", query::get_readable_roll_string(dice, &roll_choice));
+    }
+    Ok(())
 }
 
-/// Returns a HashMap of all combinations of tiles to remove from a given roll
-/// key: roll, value: Vec<Tiles>, where Tiles is the combination of tiles to remove
-fn get_tile_removal_possibilities(
-    tiles: &Tiles,
-    possible_rolls: &Vec<Uns>,
-    removal_max: &Uns,
-) -> HashMap<Uns, Vec<Tiles>> {
-    let mut trp: HashMap<Uns, Vec<Tiles>> = HashMap::new();
-    for roll in possible_rolls {
-        if roll > &0 {
-            let removals: Vec<Tiles> = r_tile_removal(tiles, roll, &removal_max);
-            trp.insert(*roll, removals);
-        } else {
-            trp.insert(*roll, vec![vec![0]]);
-        }
-    }
-    trp
+/// Serves the JSON API on a loopback port until the process stops, or returns why it can't
+fn run_serve(init_data: &InitData, port: u16) -> Result<(), String> {
+    let addr = SocketAddr::from(([127, 0, 0, 1], port));
+    let server = serve::ApiServer::bind(addr, init_data.max_states)
+        .map_err(|err| format!("Can't serve on {}: {}", addr, err))?;
+    println!("Serving on http://{}", server.get_addr());
+    server.run();
+    Ok(())
 }
 
-/// Recursive function that returns all possible combinations of tiles to remove from a given roll
-fn r_tile_removal(tiles: &[Uns], targ: &Uns, removal_max: &Uns) -> Vec<Tiles> {
-    let mut removals: Vec<Tiles> = Vec::new();
-    if targ == &0 {
-        removals.push(Vec::new());
-        return removals;
-    }
-    if removal_max == &1 {
-        for tile in tiles {
-            if tile == targ {
-                removals.push(vec![*tile]);
-            }
-        }
-    } else {
-        for tile in tiles {
-            if tile <= targ {
-                let start = tiles.iter().position(|&x| x == *tile).unwrap();
-                let new_tiles = &tiles[start + 1..];
-                let new_removal_max = if removal_max > &1 { removal_max - 1 } else { 0 };
-                let new_removals = r_tile_removal(new_tiles, &(targ - tile), &new_removal_max);
-                for mut removal in new_removals {
-                    removal.push(*tile);
-                    removals.push(removal);
-                }
-            }
-        }
-    }
-    removals
+/// Solves the game and prints what perfect play knows about a game state, or returns why it can't
+fn run_query(init_data: &InitData, tiles: &Tiles) -> Result<(), String> {
+    let mut trunk = get_trunk(try_get_solvable_game_meta(init_data)?);
+    let state_query =
+        query::query_state(&mut trunk, tiles).map_err(|err| format!("Can't query: {}", err))?;
+    print!("{}", query::get_readable_query_string(&state_query));
+    Ok(())
 }
 
 /// Creates a Vec<die values> given a min and max
 fn get_die_vals(die_min: Uns, die_max: Uns, die_val_input: Vec<Uns>) -> Vec<Uns> {
     let mut die_vals;
-    if die_val_input.len() > 0 {
+    if !die_val_input.is_empty() {
         die_vals = die_val_input;
     } else {
         die_vals = Vec::new();
@@ -518,7 +288,7 @@ fn get_die_vals(die_min: Uns, die_max: Uns, die_val_input: Vec<Uns>) -> Vec<Uns>
 /// Creates a Vec<tile values>=Tiles given a min and max
 fn get_start_tiles(tile_min: Uns, tile_max: Uns, tile_input: Vec<Uns>) -> Tiles {
     let mut start_tiles;
-    if tile_input.len() > 0 {
+    if !tile_input.is_empty() {
         start_tiles = tile_input;
     } else {
         start_tiles = Vec::new();
@@ -529,31 +299,195 @@ fn get_start_tiles(tile_min: Uns, tile_max: Uns, tile_input: Vec<Uns>) -> Tiles
     start_tiles
 }
 
+/// Returns the BoxMode named on the command line, exiting if it isn't one
+fn get_box_mode(mode: &str) -> BoxMode {
+    mode.parse().unwrap_or_else(|err| {
+        eprintln!("{}", err);
+        std::process::exit(1);
+    })
+}
+
+/// Returns the points of each tile given on the command line as tile=points pairs, exiting if they can't be read
+fn get_tile_points_arg(points: &str) -> HashMap<Uns, Uns> {
+    points
+        .split(',')
+        .filter(|entry| !entry.trim().is_empty())
+        .map(|entry| {
+            let parsed = entry.split_once('=').and_then(|(tile, tile_points)| {
+                Some((tile.trim().parse().ok()?, tile_points.trim().parse().ok()?))
+            });
+            parsed.unwrap_or_else(|| {
+                eprintln!("Unknown tile points \"{}\", expected tile=points", entry);
+                std::process::exit(1);
+            })
+        })
+        .collect()
+}
+
+/// Returns the Combiner named on the command line, exiting if it isn't one
+fn get_combiner(combine: &str) -> Combiner {
+    combine.parse().unwrap_or_else(|err| {
+        eprintln!("{}", err);
+        std::process::exit(1);
+    })
+}
+
+/// Parses the tiles of a game state given as separate arguments or split by commas, ex: 1 4 7 or 1,4,7
+fn get_tiles_arg(args: &[String]) -> Tiles {
+    args.iter()
+        .flat_map(|arg| arg.split(',').filter(|tile| !tile.is_empty()))
+        .map(|tile| {
+            tile.trim().parse().unwrap_or_else(|_| {
+                eprintln!("Invalid tile \"{}\", expected a number", tile);
+                std::process::exit(1);
+            })
+        })
+        .collect()
+}
+
+/// Parses the port to serve on, the default port if none is given
+fn get_port_arg(args: &[String]) -> u16 {
+    match args {
+        [] => DEFAULT_PORT,
+        [port] => port.parse().unwrap_or_else(|_| {
+            eprintln!("Invalid port \"{}\", expected a number up to 65535", port);
+            std::process::exit(1);
+        }),
+        _ => {
+            eprintln!(
+                "Expected at most one port to serve on, got {}",
+                args.join(" ")
+            );
+            std::process::exit(1);
+        }
+    }
+}
+
+/// Returns the RemovalRule named on the command line, exiting if it isn't one
+fn get_removal_rule(removal: &str) -> RemovalRule {
+    removal.parse().unwrap_or_else(|err| {
+        eprintln!("{}", err);
+        std::process::exit(1);
+    })
+}
+
+/// Returns the DoublesRule named on the command line, exiting if it isn't one
+fn get_doubles_rule(doubles: &str) -> DoublesRule {
+    doubles.parse().unwrap_or_else(|err| {
+        eprintln!("{}", err);
+        std::process::exit(1);
+    })
+}
+
+/// Returns a setting of the rules file, exiting if it can't be read
+fn get_rule<T>(setting: Result<T, String>) -> T {
+    setting.unwrap_or_else(|err| {
+        eprintln!("Rules file: {}", err);
+        std::process::exit(1);
+    })
+}
+
+/// Returns the Utility named on the command line, exiting if it isn't one
+fn get_utility(utility: &str) -> utility::Utility {
+    utility::get_utility(utility).unwrap_or_else(|err| {
+        eprintln!("{}", err);
+        std::process::exit(1);
+    })
+}
+
+/// Returns the Scoring named on the command line, exiting if it isn't one
+fn get_scoring(scoring: &str) -> Scoring {
+    scoring.parse().unwrap_or_else(|err| {
+        eprintln!("{}", err);
+        std::process::exit(1);
+    })
+}
+
 /// Parses command line arguments and returns them as a calculated struct
-fn parse_args() -> InitData {
-    let (args, _) = opts! {
+fn parse_args() -> (InitData, Command) {
+    let (args, command_args) = opts! {
         synopsis "A simple example.";
         version "1.0";
-        opt d_min: Uns=1, desc: "Minimum die value, increments by 1";
-        opt d_max: Uns=6, desc: "Maximum die value, increments by 1";
+        opt d_min: Option<Uns>, desc: "Minimum die value, increments by 1, 1 by default";
+        opt d_max: Option<Uns>, desc: "Maximum die value, increments by 1, 6 by default";
         opt d_direct: Vec<Uns>, desc: "Die values per die, ignores min/max", multi:true;
-        opt die_cnt: Uns=2, desc: "Number of dice";
-        opt t_min: Uns=1, desc: "Minimum tile value, increments by 1";
-        opt t_max: Uns=9, desc: "Maximum tile value, increments by 1";
-        opt t_direct: Vec<Uns>, desc: "Starting tiles, ignores min_tile and max_tile", multi:true;
-        opt max_remove: Uns=0, desc: "Maximum number of tiles to remove per turn, 0 for no limit";
+        opt die_cnt: Option<Uns>, desc: "Number of dice, 2 by default";
+        opt t_min: Option<Uns>, desc: "Minimum tile value, increments by 1, 1 by default";
+        opt t_max: Option<Uns>, desc: "Maximum tile value, increments by 1, 9 by default";
+        opt t_direct: Vec<Uns>, desc: "Starting tiles, ignores min_tile and max_tile, or the tiles still open when --board is given", multi:true;
+        opt board: Vec<Uns>, desc: "Full board of a game in progress, used for the rules and scoring while solving from the tiles still open in --t-direct", multi:true;
+        opt roll: Vec<Uns>, desc: "Dice just rolled in a game in progress, one face per die, to report the best move for", multi:true;
+        opt points: Option<String>, desc: "Points scored by tiles left open when they differ from the tile, ex: 9=18,8=16";
+        opt max_remove: Option<Uns>, desc: "Maximum number of tiles to remove per turn, 0 (the default) for no limit";
+        opt exact_count: Option<Uns>, desc: "Exact number of tiles to remove per turn, 0 (the default) for any number";
+        opt adjacent: bool=false, desc: "Only remove tiles next to each other";
+        opt include_highest: bool=false, desc: "Every removal must include the highest open tile";
+        opt one_above: Option<Uns>, desc: "Remove at most one tile above this value per turn";
+        opt rules: Option<String>, desc: "Read rules from a file of name = value lines, named like these options. Options given here win over the same rule in the file";
+        opt max_states: u64=1 << 24, desc: "Refuse to solve rules with more game states than this, to avoid running out of memory";
         opt all: bool=false, desc: "Run using all possible algorithms";
         opt naive: bool=false, desc: "Run using naive algorithm";
         opt depth: bool=false, desc: "Run using depth first search singly-threaded algorithm";
         opt parallel: bool=false, desc: "Run using parallel algorithm";
+        opt value: bool=false, desc: "Run using value iteration algorithm, required when tiles can be re-opened";
+        opt combine: Option<String>, desc: "How the dice combine into the total to flip: sum (the default), product, difference (highest minus lowest), or max";
+        opt removal: Option<String>, desc: "Which tiles a roll lets you flip: sum (the default, tiles adding up to the dice total), individual (the tile matching each die, whatever the max remove), or sum-or-individual";
+        opt doubles: Option<String>, desc: "What rolling doubles does: none (the default), reroll (may roll again instead of flipping), skip (roll again instead of losing when stuck), or double (counts as twice the total)";
+        opt stop_early: bool=false, desc: "Let the player stop before getting stuck and keep their score, getting stuck instead scores every tile";
+        opt mulligans: Option<Uns>, desc: "Number of rerolls the player may spend per game after seeing a roll, 0 by default";
+        opt match_limit: Option<Uns>, desc: "Solve a two player match of several rounds, where a player loses once the tiles they left open add up to this, 0 (the default) for a single round";
+        opt mode: Option<String>, desc: "Which way tiles flip: shut (the default), or open-and-shut (a roll that can't shut any tiles re-opens a shut tile matching it, the game is lost when it can't do either)";
+        opt cheat_sheet: Option<String>, desc: "Write a strategy cheat sheet to this file, HTML if it ends in .html, otherwise Markdown";
+        opt cheat_rows: usize=20, desc: "Number of board situations to list on the cheat sheet";
+        opt heuristics: bool=false, desc: "Compare simple memorizable heuristics against perfect play";
+        opt scoring: Option<String>, desc: "Also solve for the lowest expected score of the tiles left: pips (their sum), digits (written as one number, 1, 4, and 7 score 147), or tiles (how many are left)";
+        param command: Option<String>, desc: "query TILES... to report on a game state instead of the whole game, ex: query 1 4 7, or serve [PORT] to answer JSON requests on a local port";
+        opt utility: Option<String>, desc: "Also solve for a goal judged on the tiles left open: score (lowest expected score), payout:SCORE=PAYOUT,... (unlisted scores pay nothing), cvar:FRACTION (lowest average score of the worst games), or variance:WEIGHT (expected score plus weighted variance)";
     }
     .parse_or_exit();
 
-    let die_vals = get_die_vals(args.d_min, args.d_max, args.d_direct);
-    let die_cnt = args.die_cnt;
+    let rules = match &args.rules {
+        Some(path) => Rules::read(path).unwrap_or_else(|err| {
+            eprintln!("Could not read rules file {}: {}", path, err);
+            std::process::exit(1);
+        }),
+        None => Rules::default(),
+    };
 
-    let start_tiles = get_start_tiles(args.t_min, args.t_max, args.t_direct);
-    let max_remove = args.max_remove;
+    let die_vals = get_die_vals(
+        get_rule(rules.get_or("d_min", args.d_min, 1)),
+        get_rule(rules.get_or("d_max", args.d_max, 6)),
+        get_rule(rules.get_list_or("d_direct", args.d_direct)),
+    );
+    let die_cnt = get_rule(rules.get_or("die_cnt", args.die_cnt, 2));
+
+    let t_direct = get_rule(rules.get_list_or("t_direct", args.t_direct));
+    let board = get_rule(rules.get_list_or("board", args.board));
+    // with a board given, t_direct lists the tiles still open on it instead of the starting tiles
+    let (start_tiles, open_tiles) = if board.is_empty() {
+        let start_tiles = get_start_tiles(
+            get_rule(rules.get_or("t_min", args.t_min, 1)),
+            get_rule(rules.get_or("t_max", args.t_max, 9)),
+            t_direct,
+        );
+        (start_tiles, None)
+    } else if t_direct.is_empty() {
+        (board, None)
+    } else {
+        (board, Some(t_direct))
+    };
+    let tile_points = get_tile_points_arg(&get_rule(rules.get_or(
+        "points",
+        args.points,
+        String::new(),
+    )));
+    let max_remove = get_rule(rules.get_or("max_remove", args.max_remove, 0));
+    let removal_limits = RemovalLimits {
+        exact_count: get_rule(rules.get_or("exact_count", args.exact_count, 0)),
+        adjacent: get_rule(rules.get_flag("adjacent", args.adjacent)),
+        include_highest: get_rule(rules.get_flag("include_highest", args.include_highest)),
+        one_above: args.one_above.or(get_rule(rules.get("one_above"))),
+    };
 
     let algorithm = if args.all {
         Algorithm::All
@@ -563,77 +497,66 @@ fn parse_args() -> InitData {
         Algorithm::Depth
     } else if args.parallel {
         Algorithm::Parallel
+    } else if args.value {
+        Algorithm::Value
     } else {
         Algorithm::Default
     };
 
-    InitData {
+    let box_mode = get_box_mode(&get_rule(rules.get_or(
+        "mode",
+        args.mode,
+        "shut".to_string(),
+    )));
+    let combiner = get_combiner(&get_rule(rules.get_or(
+        "combine",
+        args.combine,
+        "sum".to_string(),
+    )));
+    let removal_rule = get_removal_rule(&get_rule(rules.get_or(
+        "removal",
+        args.removal,
+        "sum".to_string(),
+    )));
+    let doubles_rule = get_doubles_rule(&get_rule(rules.get_or(
+        "doubles",
+        args.doubles,
+        "none".to_string(),
+    )));
+
+    let command = match args.command.as_deref() {
+        None => Command::Solve,
+        Some("query") => Command::Query(get_tiles_arg(&command_args)),
+        Some("serve") => Command::Serve(get_port_arg(&command_args)),
+        Some(command) => {
+            eprintln!("Unknown command \"{}\", expected query or serve", command);
+            std::process::exit(1);
+        }
+    };
+
+    let init_data = InitData {
         die_vals,
         die_cnt,
         start_tiles,
+        open_tiles,
+        pending_roll: (!args.roll.is_empty()).then_some(args.roll),
+        tile_points,
         max_remove,
+        box_mode,
+        combiner,
+        removal_rule,
+        removal_limits,
+        doubles_rule,
+        stop_early: get_rule(rules.get_flag("stop_early", args.stop_early)),
+        mulligans: get_rule(rules.get_or("mulligans", args.mulligans, 0)),
+        match_limit: get_rule(rules.get_or("match_limit", args.match_limit, 0)),
         algorithm,
-    }
-}
-
-/// Returns a Hashmap of all possible rolls and their probabilities given some die_vals and die_cnt
-fn get_roll_probs(die_vals: &Vec<Uns>, die_cnt: Uns, sum: Uns) -> HashMap<Uns, Float> {
-    let rolls = get_srt(&get_roll_counts(die_vals, die_cnt, sum));
-    get_roll_probabilities(&rolls)
-}
-
-/// Returns a vector such that each element is a roll of the dice, repeating the sums as many times as they are rolled
-fn get_roll_counts(values: &Vec<Uns>, count: Uns, sum: Uns) -> Vec<Uns> {
-    let mut counts = Vec::new();
-    if count == 0 {
-        counts.push(sum);
-    } else {
-        for value in values {
-            counts.append(&mut get_roll_counts(&values, count - 1, sum + value));
-        }
-    }
-    counts
-}
-
-/// Returns a HashMap of all possible rolls and their probabilities given a sorted Vec of rolls
-/// key: roll, value: probability
-fn get_roll_probabilities(rolls: &Vec<Uns>) -> HashMap<Uns, Float> {
-    let mut roll_counts: HashMap<Uns, usize> = HashMap::new();
-    let mut roll_probabilities: HashMap<Uns, Float> = HashMap::new();
-    let mut total_rolls = 0;
-    for roll in rolls {
-        let count = roll_counts.entry(*roll).or_insert(0);
-        *count += 1;
-        total_rolls += 1;
-    }
-    for (roll, count) in roll_counts {
-        roll_probabilities.insert(roll, count as Float / total_rolls as Float);
-    }
-    roll_probabilities
-}
-
-fn get_game_meta() -> GameMeta {
-    let init_data = parse_args();
-
-    let die_max = get_max(&init_data.die_vals);
-
-    // todo probably can optimize with this sorted
-    // todo eventually make this where the num dice rolled is totally dynamic
-    let roll_probs_multi = get_roll_probs(&init_data.die_vals, init_data.die_cnt, 0);
-
-    let roll_probs_single = get_roll_probs(&init_data.die_vals, 1, 0);
-
-    let roll_possib = get_srt_dedup_keys(&roll_probs_multi, &roll_probs_single);
-
-    let trphm =
-        get_tile_removal_possibilities(&init_data.start_tiles, &roll_possib, &init_data.max_remove);
-
-    GameMeta {
-        die_max,
-        trphm,
-        roll_probs_single,
-        roll_probs_multi,
-        tiles: init_data.start_tiles,
-        algorithm: init_data.algorithm,
-    }
+        cheat_sheet: args.cheat_sheet,
+        cheat_rows: args.cheat_rows,
+        heuristics: args.heuristics,
+        utility: args.utility.as_deref().map(get_utility),
+        scoring: args.scoring.as_deref().map(get_scoring),
+        max_states: args.max_states,
+    };
+    (init_data, command)
 }
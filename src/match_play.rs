@@ -0,0 +1,239 @@
+use crate::{
+    get_score, get_score_chances, get_state_graph, get_trunk, graph_solve, Float, GameMeta,
+    Objective, StateGraph, Trunk, VALUE_ITERATION_MAX_SWEEPS, VALUE_ITERATION_TOLERANCE,
+};
+use std::sync::Arc;
+
+/// A match between two players, who each play a round and add the tiles they left open to their match score.
+/// Once either match score reaches the limit the lower match score wins, ties are worth half a win.
+/// A limit of 0 is a single round, won by the lower round score.
+struct Match {
+    /// The match score at which a player loses
+    limit: usize,
+    /// The opponent's chance of each round score, indexed by score, from playing to shut the box every round
+    opponent_score_chances: Vec<Float>,
+}
+
+impl Match {
+    /// Returns the player's chance of winning the match after a round, given the match win chance of every
+    /// unfinished match score and the player's chance for the match score the round started at
+    fn get_win_chance(
+        &self,
+        scores: (usize, usize),
+        win_chances: &[Vec<Float>],
+        win_chance: Float,
+        round_scores: (usize, usize),
+    ) -> Float {
+        let score = scores.0 + round_scores.0;
+        let opponent_score = scores.1 + round_scores.1;
+        if score < self.limit && opponent_score < self.limit {
+            if round_scores == (0, 0) {
+                win_chance
+            } else {
+                win_chances[score][opponent_score]
+            }
+        } else if score < opponent_score {
+            1.
+        } else if score == opponent_score {
+            0.5
+        } else {
+            0.
+        }
+    }
+
+    /// Returns the utility of each of the player's round scores from a match score, indexed by round score
+    fn get_round_utilities(
+        &self,
+        scores: (usize, usize),
+        win_chances: &[Vec<Float>],
+        win_chance: Float,
+        max_round_score: usize,
+    ) -> Vec<Float> {
+        (0..=max_round_score)
+            .map(|round_score| {
+                self.opponent_score_chances
+                    .iter()
+                    .enumerate()
+                    .map(|(opponent_round_score, chance)| {
+                        chance
+                            * self.get_win_chance(
+                                scores,
+                                win_chances,
+                                win_chance,
+                                (round_score, opponent_round_score),
+                            )
+                    })
+                    .sum()
+            })
+            .collect()
+    }
+}
+
+/// A solved match, with both the player and the opponent playing to shut the box every round,
+/// and with the player instead playing each round to win the match
+struct MatchSolution {
+    /// Match win chance from every match score, key: (player score, opponent score)
+    fixed_win_chances: Vec<Vec<Float>>,
+    /// Match win chance from every match score when the round is played to win the match
+    adapted_win_chances: Vec<Vec<Float>>,
+}
+
+/// Solves the round played from a match score, returning the round's game meta and the value of every game state
+fn get_round_solution(
+    graph: &StateGraph,
+    game_meta: &GameMeta,
+    utilities: Vec<Float>,
+) -> (GameMeta, Vec<Float>) {
+    let mut round_meta = GameMeta {
+        objective: Objective::ScoreUtility(Arc::new(utilities)),
+        mulligan_db: None,
+        ..game_meta.clone()
+    };
+    for _ in 0..game_meta.mulligans {
+        let values = graph_solve(graph, &round_meta);
+        round_meta.mulligan_db = Some(Arc::new(graph.states.iter().cloned().zip(values).collect()));
+    }
+    let values = graph_solve(graph, &round_meta);
+    (round_meta, values)
+}
+
+/// Solves every match score from the highest down, since match scores only ever go up
+fn get_match_solution(
+    game_meta: &GameMeta,
+    graph: &StateGraph,
+    match_play: &Match,
+) -> MatchSolution {
+    let limit = match_play.limit;
+    let max_round_score = get_score(&game_meta.tiles, game_meta) as usize;
+    let root_id = graph.state_ids[&game_meta.tiles];
+    let shut_chance = match_play.opponent_score_chances[0];
+
+    // a single round is still played from the match score 0 to 0
+    let score_cnt = limit.max(1);
+    let mut fixed_win_chances = vec![vec![0.; score_cnt]; score_cnt];
+    let mut adapted_win_chances = vec![vec![0.; score_cnt]; score_cnt];
+    for score in (0..score_cnt).rev() {
+        for opponent_score in (0..score_cnt).rev() {
+            let scores = (score, opponent_score);
+
+            // both players shutting the box leaves the match score as it was, so leave that round out,
+            // unless it ends a single round in a tie
+            let fixed_utilities =
+                match_play.get_round_utilities(scores, &fixed_win_chances, 0., max_round_score);
+            let repeat_chance = if limit > 0 {
+                shut_chance * shut_chance
+            } else {
+                0.
+            };
+            fixed_win_chances[score][opponent_score] = if repeat_chance < 1. {
+                fixed_utilities
+                    .iter()
+                    .zip(&match_play.opponent_score_chances)
+                    .map(|(utility, chance)| utility * chance)
+                    .sum::<Float>()
+                    / (1. - repeat_chance)
+            } else {
+                0.5
+            };
+
+            // the adapted round's value depends on itself through that same round, so iterate until it settles
+            let mut win_chance = fixed_win_chances[score][opponent_score];
+            for _ in 0..VALUE_ITERATION_MAX_SWEEPS {
+                let utilities = match_play.get_round_utilities(
+                    scores,
+                    &adapted_win_chances,
+                    win_chance,
+                    max_round_score,
+                );
+                let (_, values) = get_round_solution(graph, game_meta, utilities);
+                let change = (values[root_id] - win_chance).abs();
+                win_chance = values[root_id];
+                if change < VALUE_ITERATION_TOLERANCE {
+                    break;
+                }
+            }
+            adapted_win_chances[score][opponent_score] = win_chance;
+        }
+    }
+    MatchSolution {
+        fixed_win_chances,
+        adapted_win_chances,
+    }
+}
+
+/// Returns the shut chance and the expected score of a round played from a solved game
+fn get_round_stats(trunk: &Trunk) -> (Float, Float) {
+    let score_chances = get_score_chances(trunk);
+    let expected_score = score_chances
+        .iter()
+        .enumerate()
+        .map(|(score, chance)| score as Float * chance)
+        .sum();
+    (score_chances[0], expected_score)
+}
+
+/// Returns a readable report of a match's win chances and how the round policy changes with the match score
+pub fn get_readable_match_string(game_meta: &GameMeta) -> String {
+    let shut_trunk = get_trunk(game_meta.clone());
+    let match_play = Match {
+        limit: game_meta.match_limit as usize,
+        opponent_score_chances: get_score_chances(&shut_trunk),
+    };
+    let graph = get_state_graph(game_meta);
+    let solution = get_match_solution(game_meta, &graph, &match_play);
+    let limit = match_play.limit;
+    let max_round_score = get_score(&game_meta.tiles, game_meta) as usize;
+
+    let mut s = String::new();
+    if limit > 0 {
+        s.push_str(&format!(
+            "A player loses once the tiles they left open add up to {} or more, the lower score wins if both do\n",
+            limit
+        ));
+    } else {
+        s.push_str("A single round, the lower score wins\n");
+    }
+    s.push_str(&format!(
+        "Match win chance with both players playing to shut the box: {:.2}%\n",
+        solution.fixed_win_chances[0][0] * 100.
+    ));
+    s.push_str(&format!(
+        "Match win chance playing each round to win the match against that: {:.2}%\n",
+        solution.adapted_win_chances[0][0] * 100.
+    ));
+    let (shut_chance, expected_score) = get_round_stats(&shut_trunk);
+    s.push_str(&format!(
+        "Playing to shut the box: {:.2}% shut chance, {:.2} expected round score\n",
+        shut_chance * 100.,
+        expected_score
+    ));
+    s.push_str("Your score  Their score  Match win chance  Shut chance  Expected round score\n");
+
+    let step = (limit / 4).max(1);
+    for score in (0..limit.max(1)).step_by(step) {
+        for opponent_score in (0..limit.max(1)).step_by(step) {
+            let win_chance = solution.adapted_win_chances[score][opponent_score];
+            let utilities = match_play.get_round_utilities(
+                (score, opponent_score),
+                &solution.adapted_win_chances,
+                win_chance,
+                max_round_score,
+            );
+            let (round_meta, values) = get_round_solution(&graph, game_meta, utilities);
+            let trunk = Trunk {
+                game_meta: round_meta,
+                game_db: graph.states.iter().cloned().zip(values).collect(),
+            };
+            let (shut_chance, expected_score) = get_round_stats(&trunk);
+            s.push_str(&format!(
+                "{:>10}  {:>11}  {:>15.2}%  {:>10.2}%  {:>20.2}\n",
+                score,
+                opponent_score,
+                win_chance * 100.,
+                shut_chance * 100.,
+                expected_score
+            ));
+        }
+    }
+    s
+}
@@ -0,0 +1,148 @@
+use crate::{get_flips, get_reopen_allowed, BoxMode, GameMeta, Roll, Tiles, Uns};
+use std::collections::HashMap;
+
+/// A set of the flippable tiles, bit i set when the i-th flippable tile is in the set.
+/// Copies of a tile use the lowest bits of their run first.
+type Mask = u64;
+
+/// Generates the legal moves of a game from a per roll index of tile sets, precomputed from the starting tiles
+#[derive(Debug, Default)]
+pub struct MoveGen {
+    /// The starting tiles that some roll can flip, sorted, one per bit of a mask
+    tiles: Tiles,
+    /// The starting tiles that no roll can flip, kept in every game state
+    fixed_tiles: Tiles,
+    /// key: roll, value: every set of tiles the roll may flip, in the order of the sorted tile combinations
+    flips: HashMap<Roll, Vec<Mask>>,
+    /// key: roll, value: the distinct tiles the roll may re-open when it can't shut any, sorted
+    reopens: HashMap<Roll, Tiles>,
+}
+
+impl MoveGen {
+    /// Indexes every set of tiles each roll may flip from the starting tiles,
+    /// or returns why not when more tiles can be flipped than a mask holds
+    pub fn new(game_meta: &GameMeta) -> Result<MoveGen, String> {
+        let (tiles, fixed_tiles): (Tiles, Tiles) = game_meta.tiles.iter().partition(|tile| {
+            game_meta
+                .trphm
+                .values()
+                .any(|trps| trps.iter().any(|trp| trp.contains(tile)))
+        });
+        if tiles.len() > Mask::BITS as usize {
+            return Err(format!(
+                "At most {} tiles can be flipped, but the rules allow flipping {}",
+                Mask::BITS,
+                tiles.len()
+            ));
+        }
+        let mut move_gen = MoveGen {
+            tiles,
+            fixed_tiles,
+            flips: HashMap::new(),
+            reopens: HashMap::new(),
+        };
+        for roll in &game_meta.rolls {
+            let flips = get_flips(&move_gen.tiles, roll, game_meta)
+                .iter()
+                .map(|flip| move_gen.get_mask(flip))
+                .collect();
+            move_gen.flips.insert(roll.clone(), flips);
+            if game_meta.box_mode == BoxMode::OpenAndShut {
+                let mut reopens: Tiles = move_gen
+                    .tiles
+                    .iter()
+                    .copied()
+                    .filter(|tile| get_reopen_allowed(*tile, roll, game_meta))
+                    .collect();
+                reopens.dedup();
+                move_gen.reopens.insert(roll.clone(), reopens);
+            }
+        }
+        Ok(move_gen)
+    }
+
+    /// Returns the mask of the flippable tiles among the given sorted tiles
+    fn get_mask(&self, tiles: &Tiles) -> Mask {
+        let mut mask = 0;
+        for tile in tiles {
+            let mut bit = self.tiles.partition_point(|x| x < tile);
+            if self.tiles.get(bit) != Some(tile) {
+                continue;
+            }
+            while mask & (1 << bit) != 0 {
+                bit += 1;
+            }
+            mask |= 1 << bit;
+        }
+        mask
+    }
+
+    /// Returns the flippable tiles in a mask, sorted
+    fn get_mask_tiles(&self, mask: Mask) -> Tiles {
+        let mut tiles = Vec::with_capacity(mask.count_ones() as usize);
+        let mut rest = mask;
+        while rest != 0 {
+            tiles.push(self.tiles[rest.trailing_zeros() as usize]);
+            rest &= rest - 1;
+        }
+        tiles
+    }
+
+    /// Returns the game state of the tiles in a mask together with the fixed tiles
+    fn get_tiles(&self, mask: Mask) -> Tiles {
+        let mut tiles = self.get_mask_tiles(mask);
+        if !self.fixed_tiles.is_empty() {
+            tiles.extend(&self.fixed_tiles);
+            tiles.sort_unstable();
+        }
+        tiles
+    }
+
+    /// Returns the sets of the tiles in a mask a roll may flip, checking each with a single AND.
+    /// The highest of the tiles may be one no roll can flip, which then rules out every flip under include highest.
+    fn get_legal_flips<'a>(
+        &'a self,
+        mask: Mask,
+        highest: Option<Uns>,
+        roll: &Roll,
+        game_meta: &'a GameMeta,
+    ) -> impl Iterator<Item = Mask> + 'a {
+        let flips = self.flips.get(roll).map_or(&[][..], |flips| flips);
+        flips.iter().copied().filter(move |flip| {
+            mask & flip == *flip
+                && (!game_meta.removal_limits.include_highest || self.get_highest(*flip) == highest)
+        })
+    }
+
+    /// Returns the highest tile in a mask
+    fn get_highest(&self, mask: Mask) -> Option<Uns> {
+        mask.checked_ilog2().map(|bit| self.tiles[bit as usize])
+    }
+
+    /// Returns every game state reachable from the given tiles with a roll
+    pub fn get_next_states(&self, tiles: &Tiles, roll: &Roll, game_meta: &GameMeta) -> Vec<Tiles> {
+        let mask = self.get_mask(tiles);
+        let mut next_states: Vec<Tiles> = self
+            .get_legal_flips(mask, tiles.iter().max().copied(), roll, game_meta)
+            .map(|flip| self.get_tiles(mask ^ flip))
+            .collect();
+        if next_states.is_empty() {
+            if let Some(reopens) = self.reopens.get(roll) {
+                let shut_tiles = self.get_mask_tiles(!mask & self.get_full_mask());
+                for tile in reopens.iter().filter(|tile| shut_tiles.contains(tile)) {
+                    let mut new_tiles = tiles.clone();
+                    new_tiles.insert(new_tiles.partition_point(|x| x <= tile), *tile);
+                    next_states.push(new_tiles);
+                }
+            }
+        }
+        next_states
+    }
+
+    /// Returns the mask of every flippable tile
+    fn get_full_mask(&self) -> Mask {
+        (1 as Mask)
+            .checked_shl(self.tiles.len() as u32)
+            .map_or(Mask::MAX, |bit| bit - 1)
+    }
+}
@@ -0,0 +1,204 @@
+use crate::json_api::{get_roll_choice_json, get_rules_init_data, get_state_query_json};
+use crate::query::{get_policy, query_roll, query_state, BestMove};
+use crate::simulate::simulate;
+use crate::{get_removed_tiles, get_trunk, try_get_solvable_game_meta, Float, Tiles, Trunk, Uns};
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use pyo3::types::PyDict;
+use serde_json::Value;
+
+/// A solved game, built from rules named like the rules file settings, ex: Game(t_max=10, doubles="reroll").
+/// Rules left out are the classic rules.
+/// Tables come back as dicts of equal length columns, ready for numpy.asarray or pandas.DataFrame.
+/// Game states are rows of 0s and 1s, one column per tile of the board, 1 for open.
+#[pyclass(module = "shut_the_box_rust")]
+struct Game {
+    trunk: Trunk,
+}
+
+#[pymethods]
+impl Game {
+    #[new]
+    #[pyo3(signature = (max_states = 1 << 24, **rules))]
+    fn new(py: Python<'_>, max_states: u64, rules: Option<&Bound<'_, PyDict>>) -> PyResult<Game> {
+        let text = match rules {
+            Some(rules) => py
+                .import("json")?
+                .call_method1("dumps", (rules,))?
+                .extract::<String>()?,
+            None => "{}".to_string(),
+        };
+        let init_data = get_rules_init_data(&text, max_states).map_err(PyValueError::new_err)?;
+        let game_meta = try_get_solvable_game_meta(&init_data).map_err(PyValueError::new_err)?;
+        Ok(Game {
+            trunk: get_trunk(game_meta),
+        })
+    }
+
+    /// The starting tiles, sorted
+    #[getter]
+    fn board(&self) -> Tiles {
+        self.trunk.game_meta.tiles.clone()
+    }
+
+    /// The perfect play win chance of the starting tiles
+    #[getter]
+    fn win_chance(&self) -> Float {
+        self.trunk.game_db[&self.trunk.game_meta.tiles]
+    }
+
+    /// Returns what perfect play knows about the open tiles, the starting tiles by default
+    #[pyo3(signature = (tiles = None))]
+    fn query<'py>(&mut self, py: Python<'py>, tiles: Option<Tiles>) -> PyResult<Bound<'py, PyAny>> {
+        let tiles = tiles.unwrap_or_else(|| self.board());
+        let state_query = query_state(&mut self.trunk, &tiles).map_err(PyValueError::new_err)?;
+        get_py_json(py, &get_state_query_json(&state_query))
+    }
+
+    /// Returns the best move for the dice just rolled from the open tiles, one face per die
+    fn best_move<'py>(
+        &mut self,
+        py: Python<'py>,
+        tiles: Tiles,
+        roll: Vec<Uns>,
+    ) -> PyResult<Bound<'py, PyAny>> {
+        let roll_choice =
+            query_roll(&mut self.trunk, &tiles, &roll).map_err(PyValueError::new_err)?;
+        get_py_json(py, &get_roll_choice_json(&roll_choice))
+    }
+
+    /// Returns every solved game state and its win chance, as columns open and win_chance
+    fn win_chances<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyDict>> {
+        let mut states: Vec<(&Tiles, &Float)> = self.trunk.game_db.iter().collect();
+        states.sort_unstable_by(|a, b| a.0.cmp(b.0));
+        let columns = PyDict::new(py);
+        columns.set_item(
+            "open",
+            states
+                .iter()
+                .map(|(tiles, _)| self.get_open_row(tiles))
+                .collect::<Vec<_>>(),
+        )?;
+        columns.set_item(
+            "win_chance",
+            states
+                .iter()
+                .map(|(_, win_chance)| **win_chance)
+                .collect::<Vec<_>>(),
+        )?;
+        Ok(columns)
+    }
+
+    /// Returns perfect play from every solved game state, a row per roll of the dice it rolls.
+    /// Columns: open, single (one die rolled), roll, move, flip (the tiles flipped or re-opened), and win_chance after.
+    /// Game states where perfect play stops get a single row with the move "stop" and an empty roll.
+    fn policy<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyDict>> {
+        let tile_cnt = self.trunk.game_meta.tiles.len();
+        let (mut open, mut single, mut rolls, mut moves, mut flips, mut win_chances) =
+            (vec![], vec![], vec![], vec![], vec![], vec![]);
+        for state_policy in get_policy(&self.trunk) {
+            if state_policy.stops {
+                open.push(self.get_open_row(&state_policy.tiles));
+                single.push(false);
+                rolls.push(vec![]);
+                moves.push("stop");
+                flips.push(vec![0; tile_cnt]);
+                win_chances.push(state_policy.win_chance);
+            }
+            for choice in &state_policy.choices {
+                let (best_move, flip) = match &choice.best_move {
+                    BestMove::Flip(flip) => ("flip", self.get_open_row(flip)),
+                    BestMove::Reopen(flip) => ("open", self.get_open_row(flip)),
+                    BestMove::RollAgain => ("roll_again", vec![0; tile_cnt]),
+                    BestMove::SpendMulligan => ("spend_mulligan", vec![0; tile_cnt]),
+                    BestMove::Stuck => ("stuck", vec![0; tile_cnt]),
+                };
+                open.push(self.get_open_row(&state_policy.tiles));
+                single.push(state_policy.use_single);
+                rolls.push(choice.roll.clone());
+                moves.push(best_move);
+                flips.push(flip);
+                win_chances.push(choice.win_chance);
+            }
+        }
+        let columns = PyDict::new(py);
+        columns.set_item("open", open)?;
+        columns.set_item("single", single)?;
+        columns.set_item("roll", rolls)?;
+        columns.set_item("move", moves)?;
+        columns.set_item("flip", flips)?;
+        columns.set_item("win_chance", win_chances)?;
+        Ok(columns)
+    }
+
+    /// Plays games with perfect play from the open tiles, the starting tiles by default.
+    /// The same seed plays the same games. Columns: won, score, rolls, and open at the end.
+    #[pyo3(signature = (games = 1000, seed = 0, tiles = None))]
+    fn simulate<'py>(
+        &mut self,
+        py: Python<'py>,
+        games: usize,
+        seed: u64,
+        tiles: Option<Tiles>,
+    ) -> PyResult<Bound<'py, PyDict>> {
+        let tiles = tiles.unwrap_or_else(|| self.board());
+        let games =
+            simulate(&mut self.trunk, &tiles, games, seed).map_err(PyValueError::new_err)?;
+        let columns = PyDict::new(py);
+        columns.set_item(
+            "won",
+            games
+                .iter()
+                .map(|game| game.tiles.is_empty())
+                .collect::<Vec<_>>(),
+        )?;
+        columns.set_item(
+            "score",
+            games.iter().map(|game| game.score).collect::<Vec<_>>(),
+        )?;
+        columns.set_item(
+            "rolls",
+            games.iter().map(|game| game.roll_cnt).collect::<Vec<_>>(),
+        )?;
+        columns.set_item(
+            "open",
+            games
+                .iter()
+                .map(|game| self.get_open_row(&game.tiles))
+                .collect::<Vec<_>>(),
+        )?;
+        Ok(columns)
+    }
+}
+
+impl Game {
+    /// Returns a 1 for each tile of the board among the given tiles and a 0 for the rest,
+    /// marking the lowest copies first when the board repeats a tile
+    fn get_open_row(&self, tiles: &Tiles) -> Vec<Uns> {
+        let mut left = tiles.clone();
+        self.trunk
+            .game_meta
+            .tiles
+            .iter()
+            .map(|tile| match get_removed_tiles(&left, &vec![*tile]) {
+                Some(next_left) => {
+                    left = next_left;
+                    1
+                }
+                None => 0,
+            })
+            .collect()
+    }
+}
+
+/// Returns a JSON value as the matching Python dicts and lists
+fn get_py_json<'py>(py: Python<'py>, value: &Value) -> PyResult<Bound<'py, PyAny>> {
+    py.import("json")?
+        .call_method1("loads", (value.to_string(),))
+}
+
+/// Solves Shut the Box games for analysis in Python
+#[pymodule]
+fn shut_the_box_rust(module: &Bound<'_, PyModule>) -> PyResult<()> {
+    module.add_class::<Game>()
+}
@@ -0,0 +1,310 @@
+use crate::{
+    depth_solve, get_dice_roll, get_dice_win_chances, get_end_chances_from, get_flipped_tiles,
+    get_mulligan_win_chance, get_removed_tiles, get_roll_win_chance, get_rolls_again, get_score,
+    get_single_legality, get_solved_best_states, get_spends_mulligan, get_stops,
+    get_stuck_win_chance, get_tiles_string, Float, GameMeta, Roll, Tiles, Trunk, Uns,
+};
+use std::collections::HashMap;
+
+/// What perfect play does after a given roll
+#[derive(Debug, Clone, PartialEq)]
+pub enum BestMove {
+    /// Flip these tiles, highest first
+    Flip(Tiles),
+    /// Re-open these shut tiles, highest first, when the rules allow it
+    Reopen(Tiles),
+    /// Roll again from the same board, when doubles allow it
+    RollAgain,
+    /// Spend a mulligan to roll again from the same board
+    SpendMulligan,
+    /// No tiles can be flipped
+    Stuck,
+}
+
+/// The best move for a roll and the win chance it leaves
+#[derive(Debug, Clone)]
+pub struct RollChoice {
+    pub roll: Roll,
+    pub best_move: BestMove,
+    /// Win chance after making the best move
+    pub win_chance: Float,
+}
+
+/// Everything perfect play knows about a game state of a solved game
+#[derive(Debug, Clone)]
+pub struct StateQuery {
+    /// The open tiles
+    pub tiles: Tiles,
+    /// Perfect play win chance from this board, or its value under the game's objective
+    pub win_chance: Float,
+    /// The expected score of the tiles left at the end, every tile when going bust after stopping early was allowed,
+    /// None when tiles can be re-opened or mulligans are left,
+    /// since spent mulligans aren't tracked from one board to the next
+    pub expected_score: Option<Float>,
+    /// Whether rolling a single die is allowed from this board
+    pub single_legal: bool,
+    /// Whether perfect play rolls a single die from this board
+    pub use_single: bool,
+    /// Whether perfect play stops here and keeps the score, when stopping early is allowed
+    pub stops: bool,
+    /// The best move for each roll of the dice perfect play rolls, sorted by roll, empty if it stops
+    pub choices: Vec<RollChoice>,
+}
+
+/// What perfect play does from a game state of a solved game
+#[derive(Debug, Clone)]
+pub struct StatePolicy {
+    /// The open tiles
+    pub tiles: Tiles,
+    /// Perfect play win chance from this board, or its value under the game's objective
+    pub win_chance: Float,
+    /// Whether perfect play rolls a single die from this board
+    pub use_single: bool,
+    /// Whether perfect play stops here and keeps the score, when stopping early is allowed
+    pub stops: bool,
+    /// The best move for each roll of the dice perfect play rolls, sorted by roll, empty if it stops
+    pub choices: Vec<RollChoice>,
+}
+
+/// Looks up a game state of a solved game, any sub-board of the starting tiles.
+/// Game states the solve never reached are solved on the spot and added to the trunk, without re-solving the rest.
+pub fn query_state(trunk: &mut Trunk, tiles: &Tiles) -> Result<StateQuery, String> {
+    let tiles = get_solved_tiles(trunk, tiles)?;
+    let game_meta = &trunk.game_meta;
+    let state_policy = get_state_policy(&tiles, trunk);
+    let expected_score = if game_meta.box_mode.get_has_cycles() || game_meta.mulligan_db.is_some() {
+        None
+    } else {
+        let end_chances = get_end_chances_from(&tiles, trunk);
+        Some(
+            end_chances
+                .iter()
+                .map(|(tiles, end_chance)| end_chance * get_score(tiles, game_meta) as Float)
+                .sum(),
+        )
+    };
+
+    Ok(StateQuery {
+        win_chance: state_policy.win_chance,
+        expected_score,
+        single_legal: get_single_legality(&tiles, &game_meta.die_max),
+        use_single: state_policy.use_single,
+        stops: state_policy.stops,
+        choices: state_policy.choices,
+        tiles,
+    })
+}
+
+/// Returns what perfect play does from every solved game state of a solved game, sorted by tiles
+pub fn get_policy(trunk: &Trunk) -> Vec<StatePolicy> {
+    let mut states: Vec<&Tiles> = trunk.game_db.keys().collect();
+    states.sort_unstable();
+    states
+        .into_iter()
+        .map(|tiles| get_state_policy(tiles, trunk))
+        .collect()
+}
+
+/// Returns what perfect play does from a solved game state
+pub(crate) fn get_state_policy(tiles: &Tiles, trunk: &Trunk) -> StatePolicy {
+    let game_meta = &trunk.game_meta;
+    let win_chance = trunk.game_db[tiles];
+    let best_states_hm = get_solved_best_states(tiles, game_meta, &trunk.game_db);
+    let (win_chance_single, win_chance_multi) =
+        get_dice_win_chances(tiles, &best_states_hm, game_meta);
+    let use_single = win_chance_single > win_chance_multi;
+    let stops = get_stops(tiles, win_chance_single, win_chance_multi, game_meta);
+
+    let mut choices = Vec::new();
+    if !tiles.is_empty() && !stops {
+        let roll_probs = if use_single {
+            &game_meta.roll_probs_single
+        } else {
+            &game_meta.roll_probs_multi
+        };
+        let mut rolls: Vec<&Roll> = roll_probs.keys().collect();
+        rolls.sort_unstable();
+        for roll in rolls {
+            choices.push(get_roll_choice(
+                tiles,
+                roll,
+                &best_states_hm,
+                win_chance,
+                game_meta,
+            ));
+        }
+    }
+    StatePolicy {
+        tiles: tiles.clone(),
+        win_chance,
+        use_single,
+        stops,
+        choices,
+    }
+}
+
+/// Looks up the best move for dice just rolled from a game state of a solved game, given the face of each die.
+/// Rolling one die is allowed whenever the rules allow it, even when perfect play would roll them all.
+pub fn query_roll(trunk: &mut Trunk, tiles: &Tiles, dice: &[Uns]) -> Result<RollChoice, String> {
+    let tiles = get_solved_tiles(trunk, tiles)?;
+    let game_meta = &trunk.game_meta;
+    if tiles.is_empty() {
+        return Err("the box is already shut".to_string());
+    }
+    let roll_probs = if dice.len() == 1 {
+        if !get_single_legality(&tiles, &game_meta.die_max) {
+            return Err(format!(
+                "one die can't be rolled with tiles {:?} open",
+                tiles
+            ));
+        }
+        &game_meta.roll_probs_single
+    } else if dice.len() == game_meta.die_cnt as usize {
+        &game_meta.roll_probs_multi
+    } else {
+        return Err(format!(
+            "rolled {} dice, expected 1 or {}",
+            dice.len(),
+            game_meta.die_cnt
+        ));
+    };
+    // a single die counts as its face, so these are exactly the faces of a die
+    if let Some(face) = dice
+        .iter()
+        .find(|face| !game_meta.roll_probs_single.contains_key(&vec![**face]))
+    {
+        return Err(format!("no die has a face of {}", face));
+    }
+    let roll = get_dice_roll(dice, game_meta);
+    if !roll_probs.contains_key(&roll) {
+        return Err(format!("dice {:?} can't be rolled together", dice));
+    }
+
+    let win_chance = trunk.game_db[&tiles];
+    let best_states_hm = get_solved_best_states(&tiles, game_meta, &trunk.game_db);
+    Ok(get_roll_choice(
+        &tiles,
+        &roll,
+        &best_states_hm,
+        win_chance,
+        game_meta,
+    ))
+}
+
+/// Returns the sorted tiles of a game state, solving it on the spot if the solve never reached it.
+/// Errors if the tiles aren't part of the starting tiles, or can't be solved on their own.
+pub(crate) fn get_solved_tiles(trunk: &mut Trunk, tiles: &Tiles) -> Result<Tiles, String> {
+    let mut tiles = tiles.clone();
+    tiles.sort_unstable();
+    let game_meta = &trunk.game_meta;
+    if get_removed_tiles(&game_meta.tiles, &tiles).is_none() {
+        return Err(format!(
+            "tiles {:?} aren't part of the starting tiles {:?}",
+            tiles, game_meta.tiles
+        ));
+    }
+    if !trunk.game_db.contains_key(&tiles) {
+        if game_meta.box_mode.get_has_cycles() || game_meta.mulligan_db.is_some() {
+            return Err(format!(
+                "tiles {:?} can't be reached from the starting tiles",
+                tiles
+            ));
+        }
+        depth_solve(tiles.clone(), &trunk.game_meta, &mut trunk.game_db);
+    }
+    Ok(tiles)
+}
+
+/// Returns the best move after a roll from a game state, and the win chance it leaves
+fn get_roll_choice(
+    tiles: &Tiles,
+    roll: &Roll,
+    best_states_hm: &HashMap<Roll, (Tiles, Float)>,
+    win_chance: Float,
+    game_meta: &GameMeta,
+) -> RollChoice {
+    let best_move = if get_spends_mulligan(tiles, roll, best_states_hm, win_chance, game_meta) {
+        BestMove::SpendMulligan
+    } else if get_rolls_again(roll, best_states_hm, win_chance, game_meta) {
+        BestMove::RollAgain
+    } else {
+        match best_states_hm.get(roll) {
+            Some((state, _)) if state.len() > tiles.len() => {
+                BestMove::Reopen(get_flipped_tiles(state, tiles))
+            }
+            Some((state, _)) => BestMove::Flip(get_flipped_tiles(tiles, state)),
+            None => BestMove::Stuck,
+        }
+    };
+    let best_win_chance = best_states_hm.get(roll).map(|(_, win_chance)| *win_chance);
+    RollChoice {
+        roll: roll.clone(),
+        best_move,
+        win_chance: get_roll_win_chance(
+            roll,
+            best_win_chance,
+            get_stuck_win_chance(tiles, game_meta),
+            win_chance,
+            get_mulligan_win_chance(tiles, game_meta),
+            game_meta,
+        ),
+    }
+}
+
+/// Returns a readable report of a game state of a solved game
+pub fn get_readable_query_string(query: &StateQuery) -> String {
+    let mut s = String::new();
+    s.push_str(&format!(
+        "Tiles {}: {:.2}% win chance",
+        get_tiles_string(&query.tiles),
+        query.win_chance * 100.
+    ));
+    if let Some(expected_score) = query.expected_score {
+        s.push_str(&format!(", expected score {:.2}", expected_score));
+    }
+    s.push('\n');
+    if query.tiles.is_empty() {
+        s.push_str("The box is shut\n");
+        return s;
+    }
+    if query.stops {
+        s.push_str("Stop and keep the score\n");
+        return s;
+    }
+    s.push_str(match (query.single_legal, query.use_single) {
+        (true, true) => "Roll one die\n",
+        (true, false) => "Roll all dice, though one die is allowed\n",
+        (false, _) => "Roll all dice\n",
+    });
+    s.push_str("Roll  Best move         Win chance after\n");
+    for choice in &query.choices {
+        s.push_str(&format!(
+            "{:>4}  {:<16}  {:>15.2}%\n",
+            get_tiles_string(&choice.roll),
+            get_best_move_string(&choice.best_move),
+            choice.win_chance * 100.
+        ));
+    }
+    s
+}
+
+/// Returns a readable report of the best move for dice just rolled
+pub fn get_readable_roll_string(dice: &[Uns], choice: &RollChoice) -> String {
+    format!(
+        "Rolled {}: {}, {:.2}% win chance after\n",
+        get_tiles_string(dice),
+        get_best_move_string(&choice.best_move),
+        choice.win_chance * 100.
+    )
+}
+
+/// Returns a best move as a short readable String, ex: "flip 9"
+fn get_best_move_string(best_move: &BestMove) -> String {
+    match best_move {
+        BestMove::Flip(flip) => format!("flip {}", get_tiles_string(flip)),
+        BestMove::Reopen(flip) => format!("open {}", get_tiles_string(flip)),
+        BestMove::RollAgain => "roll again".to_string(),
+        BestMove::SpendMulligan => "spend a mulligan".to_string(),
+        BestMove::Stuck => "stuck".to_string(),
+    }
+}
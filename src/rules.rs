@@ -0,0 +1,131 @@
+use std::collections::HashMap;
+use std::str::FromStr;
+
+/// Every rule a rules file may set, named after the matching command line option
+const RULE_NAMES: [&str; 21] = [
+    "d_min",
+    "d_max",
+    "d_direct",
+    "die_cnt",
+    "t_min",
+    "t_max",
+    "t_direct",
+    "board",
+    "points",
+    "max_remove",
+    "exact_count",
+    "adjacent",
+    "include_highest",
+    "one_above",
+    "combine",
+    "removal",
+    "doubles",
+    "stop_early",
+    "mulligans",
+    "mode",
+    "match_limit",
+];
+
+/// Game rules read from a rules file, used for the matching command line options that aren't given
+#[derive(Debug, Default)]
+pub struct Rules {
+    /// key: rule name, value: its setting as written
+    settings: HashMap<String, String>,
+}
+
+impl Rules {
+    /// Reads a rules file made of `name = value` lines, where # starts a comment
+    pub fn read(path: &str) -> Result<Rules, String> {
+        std::fs::read_to_string(path)
+            .map_err(|err| err.to_string())?
+            .parse()
+    }
+
+    /// Returns the setting of a rule if the file sets it, or why it can't be read as the expected type
+    pub fn get<T: FromStr>(&self, name: &str) -> Result<Option<T>, String> {
+        self.settings
+            .get(name)
+            .map(|value| get_value(value, name))
+            .transpose()
+    }
+
+    /// Returns the setting of a list rule if the file sets it, with values split by spaces or commas
+    pub fn get_list<T: FromStr>(&self, name: &str) -> Result<Option<Vec<T>>, String> {
+        self.settings
+            .get(name)
+            .map(|value| {
+                value
+                    .split(|c: char| c == ',' || c.is_whitespace())
+                    .filter(|item| !item.is_empty())
+                    .map(|item| get_value(item, name))
+                    .collect()
+            })
+            .transpose()
+    }
+
+    /// Returns the command line value if one was given, which wins over the file,
+    /// or else the setting of the rule if the file sets it, or else the default.
+    /// A setting that can't be read is an error either way.
+    pub fn get_or<T: FromStr>(
+        &self,
+        name: &str,
+        cli_value: Option<T>,
+        default: T,
+    ) -> Result<T, String> {
+        let setting = self.get(name)?;
+        Ok(cli_value.or(setting).unwrap_or(default))
+    }
+
+    /// Returns the command line values if any were given, which win over the file,
+    /// or else the setting of the list rule if the file sets it, or else no values
+    pub fn get_list_or<T: FromStr>(
+        &self,
+        name: &str,
+        cli_values: Vec<T>,
+    ) -> Result<Vec<T>, String> {
+        let setting = self.get_list(name)?;
+        if cli_values.is_empty() {
+            Ok(setting.unwrap_or_default())
+        } else {
+            Ok(cli_values)
+        }
+    }
+
+    /// Returns whether a flag is set on the command line or in the file,
+    /// a flag given on the command line can't be turned off by the file
+    pub fn get_flag(&self, name: &str, cli_flag: bool) -> Result<bool, String> {
+        self.get_or(name, cli_flag.then_some(true), false)
+    }
+}
+
+impl FromStr for Rules {
+    type Err = String;
+
+    /// Reads the text of a rules file.
+    /// Names are the long command line options, with either - or _ between words.
+    fn from_str(text: &str) -> Result<Rules, String> {
+        let mut settings = HashMap::new();
+        for (line_id, line) in text.lines().enumerate() {
+            let line = line.split('#').next().unwrap().trim();
+            if line.is_empty() {
+                continue;
+            }
+            let (name, value) = line
+                .split_once('=')
+                .ok_or(format!("line {}: expected name = value", line_id + 1))?;
+            let name = name.trim().replace('-', "_");
+            if !RULE_NAMES.contains(&name.as_str()) {
+                return Err(format!("line {}: unknown rule \"{}\"", line_id + 1, name));
+            }
+            settings.insert(name, value.trim().to_string());
+        }
+        Ok(Rules { settings })
+    }
+}
+
+/// Reads a single value of a rule, or returns why it can't be read as the expected type
+fn get_value<T: FromStr>(value: &str, name: &str) -> Result<T, String> {
+    value
+        .parse()
+        .map_err(|_| format!("can't read \"{}\" for {}", value, name))
+}
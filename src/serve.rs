@@ -0,0 +1,55 @@
+use crate::json_api::{get_error_json, JsonApi};
+use std::net::SocketAddr;
+
+/// A local HTTP server answering JSON requests about solved games.
+/// Solved games are kept in memory up to the game state limit, forgetting the ones solved longest ago first.
+pub struct ApiServer {
+    server: tiny_http::Server,
+    json_api: JsonApi,
+}
+
+impl ApiServer {
+    /// Starts listening on a loopback address, port 0 picks any free port
+    pub fn bind(addr: SocketAddr, max_states: u64) -> Result<ApiServer, String> {
+        if !addr.ip().is_loopback() {
+            return Err(format!("{} isn't a loopback address", addr.ip()));
+        }
+        let server = tiny_http::Server::http(addr).map_err(|err| err.to_string())?;
+        Ok(ApiServer {
+            server,
+            json_api: JsonApi::new(max_states),
+        })
+    }
+
+    /// Returns the address the server listens on
+    pub fn get_addr(&self) -> SocketAddr {
+        self.server.server_addr().to_ip().unwrap()
+    }
+
+    /// Answers requests one at a time until the process stops
+    pub fn run(mut self) {
+        for mut request in self.server.incoming_requests() {
+            let (status, body) = if *request.method() != tiny_http::Method::Post {
+                (
+                    405,
+                    get_error_json("only POST requests are answered").to_string(),
+                )
+            } else {
+                let mut text = String::new();
+                match request.as_reader().read_to_string(&mut text) {
+                    Ok(_) => self.json_api.answer(request.url(), &text),
+                    Err(err) => (400, get_error_json(&err.to_string()).to_string()),
+                }
+            };
+            println!("{} {} {}", request.method(), request.url(), status);
+            let content_type =
+                tiny_http::Header::from_bytes("Content-Type", "application/json").unwrap();
+            let response = tiny_http::Response::from_string(body)
+                .with_status_code(status)
+                .with_header(content_type);
+            if let Err(err) = request.respond(response) {
+                eprintln!("Could not respond: {}", err);
+            }
+        }
+    }
+}
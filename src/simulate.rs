@@ -0,0 +1,122 @@
+use crate::query::{get_solved_tiles, get_state_policy, BestMove, RollChoice, StatePolicy};
+use crate::{get_removed_tiles, get_score, get_srt, Float, Roll, Tiles, Trunk, Uns};
+use std::collections::HashMap;
+
+/// Games are cut off after this many rolls, since perfect play can go around in circles when tiles can be re-opened
+const MAX_ROLLS: Uns = 10_000;
+
+/// One game played out with perfect play
+#[derive(Debug, Clone)]
+pub struct SimulatedGame {
+    /// The tiles open when the game ended, empty if the box was shut
+    pub tiles: Tiles,
+    /// The points of the tiles left, or of every tile when stopping early is allowed and the game went bust
+    pub score: Uns,
+    /// The number of times the dice were rolled
+    pub roll_cnt: Uns,
+}
+
+/// A small seeded random number generator (SplitMix64), so games play out the same on every platform
+struct Rng(u64);
+
+impl Rng {
+    fn get_next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    /// Returns a number in [0, 1)
+    fn get_unit(&mut self) -> Float {
+        (self.get_next() >> 11) as Float / (1u64 << 53) as Float
+    }
+}
+
+/// Plays games of a solved game from the given tiles with perfect play, rolling the dice from a seeded generator,
+/// so the same seed plays the same games.
+/// Games with mulligans can't be played out, since playing on after spending one needs the solve with one less.
+pub fn simulate(
+    trunk: &mut Trunk,
+    tiles: &Tiles,
+    game_cnt: usize,
+    seed: u64,
+) -> Result<Vec<SimulatedGame>, String> {
+    if trunk.game_meta.mulligans > 0 {
+        return Err("games with mulligans can't be played out".to_string());
+    }
+    let tiles = get_solved_tiles(trunk, tiles)?;
+    let mut rng = Rng(seed);
+    // key: tiles, value: what perfect play does from them, found the first time a game gets there
+    let mut policies: HashMap<Tiles, StatePolicy> = HashMap::new();
+    Ok((0..game_cnt)
+        .map(|_| play_game(&tiles, trunk, &mut policies, &mut rng))
+        .collect())
+}
+
+/// Plays a single game out from the given tiles
+fn play_game(
+    tiles: &Tiles,
+    trunk: &Trunk,
+    policies: &mut HashMap<Tiles, StatePolicy>,
+    rng: &mut Rng,
+) -> SimulatedGame {
+    let game_meta = &trunk.game_meta;
+    let mut tiles = tiles.clone();
+    let mut roll_cnt = 0;
+    while !tiles.is_empty() && roll_cnt < MAX_ROLLS {
+        let state_policy = policies
+            .entry(tiles.clone())
+            .or_insert_with(|| get_state_policy(&tiles, trunk));
+        if state_policy.stops {
+            break;
+        }
+        let roll_probs = if state_policy.use_single {
+            &game_meta.roll_probs_single
+        } else {
+            &game_meta.roll_probs_multi
+        };
+        let choice = get_rolled_choice(&state_policy.choices, roll_probs, rng);
+        roll_cnt += 1;
+        match &choice.best_move {
+            BestMove::Flip(flip) => tiles = get_removed_tiles(&tiles, flip).unwrap(),
+            BestMove::Reopen(flip) => tiles = get_srt(&[&tiles[..], flip].concat()),
+            BestMove::RollAgain | BestMove::SpendMulligan => {}
+            BestMove::Stuck => {
+                let score = if game_meta.stop_early {
+                    get_score(&game_meta.tiles, game_meta)
+                } else {
+                    get_score(&tiles, game_meta)
+                };
+                return SimulatedGame {
+                    tiles,
+                    score,
+                    roll_cnt,
+                };
+            }
+        }
+    }
+    SimulatedGame {
+        score: get_score(&tiles, game_meta),
+        tiles,
+        roll_cnt,
+    }
+}
+
+/// Rolls the dice and returns the choice for the roll that came up
+fn get_rolled_choice<'a>(
+    choices: &'a [RollChoice],
+    roll_probs: &HashMap<Roll, Float>,
+    rng: &mut Rng,
+) -> &'a RollChoice {
+    let mut unit = rng.get_unit();
+    for choice in choices {
+        unit -= roll_probs[&choice.roll];
+        if unit < 0. {
+            return choice;
+        }
+    }
+    // rounding can leave a sliver past the last roll
+    choices.last().unwrap()
+}
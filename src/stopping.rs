@@ -0,0 +1,88 @@
+use crate::{
+    get_dice_win_chances, get_end_chances, get_objective_trunk, get_score, get_scoring_score,
+    get_solved_best_states, get_stops, Float, GameMeta, Objective, Scoring, Tiles, Trunk,
+};
+
+/// Returns whether perfect play stops early from a game state of a solved game
+fn get_trunk_stops(tiles: &Tiles, trunk: &Trunk) -> bool {
+    let game_meta = &trunk.game_meta;
+    let best_states_hm = get_solved_best_states(tiles, game_meta, &trunk.game_db);
+    let (win_chance_single, win_chance_multi) =
+        get_dice_win_chances(tiles, &best_states_hm, game_meta);
+    get_stops(tiles, win_chance_single, win_chance_multi, game_meta)
+}
+
+/// Returns a readable report of when to stop early for the lowest expected score under a scoring
+pub fn get_readable_stopping_string(game_meta: &GameMeta, scoring: Scoring) -> String {
+    let trunk = get_objective_trunk(game_meta, Objective::LowScore(scoring));
+    let win_trunk = get_objective_trunk(game_meta, Objective::WinChance);
+    let bust_score = get_scoring_score(&game_meta.tiles, scoring, game_meta);
+
+    let mut stop_chance = 0.;
+    let mut bust_chance = 0.;
+    let mut shut_chance = 0.;
+    let mut expected_score = 0.;
+    // going bust is scored with the starting tiles, the only game state other than a stop or a shut box a game ends at
+    for (tiles, end_chance) in get_end_chances(&trunk) {
+        expected_score += end_chance * get_scoring_score(&tiles, scoring, game_meta);
+        if tiles.is_empty() {
+            shut_chance += end_chance;
+        } else if get_trunk_stops(&tiles, &trunk) {
+            stop_chance += end_chance;
+        } else {
+            bust_chance += end_chance;
+        }
+    }
+    let never_stop_score = (1. - win_trunk.game_db[&game_meta.tiles]) * bust_score;
+
+    let mut s = String::new();
+    s.push_str(&format!(
+        "Scoring {}, going bust scores {}\n",
+        scoring.get_description(),
+        bust_score
+    ));
+    s.push_str(&format!(
+        "Expected score stopping at the best time: {:.4}\n",
+        expected_score
+    ));
+    s.push_str(&format!(
+        "Expected score never stopping: {:.4}\n",
+        never_stop_score
+    ));
+    s.push_str(&format!(
+        "Stop chance: {:.2}%, bust chance: {:.2}%, shut chance: {:.2}%\n",
+        stop_chance * 100.,
+        bust_chance * 100.,
+        shut_chance * 100.
+    ));
+
+    // the stopping region, grouped by the points of the open tiles
+    let max_sum = get_score(&game_meta.tiles, game_meta) as usize;
+    let mut board_cnts = vec![0; max_sum + 1];
+    let mut stop_cnts = vec![0; max_sum + 1];
+    for tiles in trunk.game_db.keys() {
+        if tiles.is_empty() {
+            continue;
+        }
+        let sum = get_score(tiles, game_meta) as usize;
+        board_cnts[sum] += 1;
+        if get_trunk_stops(tiles, &trunk) {
+            stop_cnts[sum] += 1;
+        }
+    }
+    s.push_str("Open tiles are worth  Stop with\n");
+    for (sum, (board_cnt, stop_cnt)) in board_cnts.iter().zip(&stop_cnts).enumerate() {
+        if *board_cnt == 0 {
+            continue;
+        }
+        let share = *stop_cnt as Float / *board_cnt as Float;
+        s.push_str(&format!(
+            "{:>20}  {:>3} of {:>3} boards {}\n",
+            sum,
+            stop_cnt,
+            board_cnt,
+            "#".repeat((share * 20.).round() as usize)
+        ));
+    }
+    s
+}
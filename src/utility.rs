@@ -0,0 +1,470 @@
+use crate::{
+    get_dice_win_chances, get_end_chances, get_flipped_tiles, get_objective_trunk,
+    get_reach_chances, get_score, get_score_chances, get_scoring_score, get_single_legality,
+    get_solved_best_states, get_tiles_string, Float, GameMeta, Objective, Roll, Scoring, Tiles,
+    Trunk, Uns, TIE_TOLERANCE,
+};
+use std::sync::Arc;
+
+/// The spacing of the means tried when penalizing variance
+const MEAN_STEP: Float = 0.25;
+/// The most solves a goal may take, one for each threshold or mean tried
+const MAX_GOAL_SOLVES: usize = 1_000;
+
+/// What a player wants out of a game besides shutting the box, judged on the score left at the end
+#[derive(Debug, Clone)]
+pub enum Utility {
+    /// A payout for each listed score, unlisted scores pay nothing
+    Payout(Vec<(Uns, Float)>),
+    /// The lowest expected score
+    LowScore,
+    /// The lowest average score over this fraction of the worst games (conditional value at risk)
+    Cvar(Float),
+    /// The lowest expected score plus this many times its variance, close to the lowest as get_utilities explains
+    MeanVariance(Float),
+}
+
+impl Utility {
+    /// Returns a readable description of the goal
+    fn get_description(&self) -> String {
+        match self {
+            Utility::Payout(_) => "highest expected payout".to_string(),
+            Utility::LowScore => "lowest expected score".to_string(),
+            Utility::Cvar(alpha) => format!(
+                "lowest average score over the worst {}% of games",
+                alpha * 100.
+            ),
+            Utility::MeanVariance(lambda) => {
+                format!("lowest expected score plus {} times its variance", lambda)
+            }
+        }
+    }
+
+    /// Returns how many games are solved to find the best play for the goal, with scores up to max_score
+    fn get_solve_cnt(&self, max_score: usize) -> usize {
+        match self {
+            Utility::Payout(_) | Utility::LowScore => 1,
+            Utility::Cvar(_) => max_score + 1,
+            Utility::MeanVariance(_) => get_mean_cnt(max_score) + 1,
+        }
+    }
+
+    /// Returns whether lower goal values are better
+    fn get_lower_is_better(&self) -> bool {
+        !matches!(self, Utility::Payout(_))
+    }
+
+    /// Returns how well a game with the given chance of each score meets the goal
+    fn get_goal_value(&self, score_chances: &[Float]) -> Float {
+        let expected_score = get_expected_score(score_chances);
+        match self {
+            Utility::Payout(_) => get_utilities(self, score_chances.len() - 1)[0]
+                .iter()
+                .zip(score_chances)
+                .map(|(payout, chance)| payout * chance)
+                .sum(),
+            Utility::LowScore => expected_score,
+            Utility::Cvar(alpha) => {
+                // the worst games are the highest scores, average them until alpha of the games are covered
+                let mut remaining = *alpha;
+                let mut total = 0.;
+                for (score, chance) in score_chances.iter().enumerate().rev() {
+                    let used = chance.min(remaining);
+                    total += used * score as Float;
+                    remaining -= used;
+                }
+                total / alpha
+            }
+            Utility::MeanVariance(lambda) => {
+                let variance: Float = score_chances
+                    .iter()
+                    .enumerate()
+                    .map(|(score, chance)| chance * (score as Float - expected_score).powi(2))
+                    .sum();
+                expected_score + lambda * variance
+            }
+        }
+    }
+}
+
+/// Returns the expected score of a game with the given chance of each score
+fn get_expected_score(score_chances: &[Float]) -> Float {
+    score_chances
+        .iter()
+        .enumerate()
+        .map(|(score, chance)| score as Float * chance)
+        .sum()
+}
+
+/// Returns how many means a quarter apart after 0 are tried when penalizing variance, up to the highest score
+fn get_mean_cnt(max_score: usize) -> usize {
+    (max_score as Float / MEAN_STEP) as usize
+}
+
+/// Returns the utilities of each score to maximize in expectation, indexed by score.
+/// Goals that aren't an expected utility are the best of several, so each is solved and the best is kept:
+/// conditional value at risk is the best of c + E[(score - c)^+] / alpha over every whole threshold c,
+/// which is exact since the best threshold is a score.
+/// Variance is E[(score - m)^2] at the best mean m, and only the means MEAN_STEP apart are tried.
+/// Play for the tried mean closest to the best play's own mean is off by at most MEAN_STEP / 2 from it,
+/// so the goal value found is within the weight times (MEAN_STEP / 2)^2 of the lowest.
+fn get_utilities(utility: &Utility, max_score: usize) -> Vec<Vec<Float>> {
+    let scores = 0..=max_score;
+    match utility {
+        Utility::Payout(payouts) => {
+            let mut payout_utilities = vec![0.; max_score + 1];
+            for (score, payout) in payouts {
+                if let Some(payout_utility) = payout_utilities.get_mut(*score as usize) {
+                    *payout_utility = *payout;
+                }
+            }
+            vec![payout_utilities]
+        }
+        Utility::LowScore => vec![scores.map(|score| -(score as Float)).collect()],
+        Utility::Cvar(alpha) => (0..=max_score)
+            .map(|threshold| {
+                scores
+                    .clone()
+                    .map(|score| {
+                        let over = score.saturating_sub(threshold) as Float;
+                        -(threshold as Float + over / alpha)
+                    })
+                    .collect()
+            })
+            .collect(),
+        Utility::MeanVariance(lambda) => (0..=get_mean_cnt(max_score))
+            .map(|mean_id| {
+                let mean = mean_id as Float * MEAN_STEP;
+                scores
+                    .clone()
+                    .map(|score| {
+                        let score = score as Float;
+                        -(score + lambda * (score - mean).powi(2))
+                    })
+                    .collect()
+            })
+            .collect(),
+    }
+}
+
+/// Returns the solved game that best meets the goal
+fn get_utility_trunk(game_meta: &GameMeta, utility: &Utility) -> Trunk {
+    let max_score = get_score(&game_meta.tiles, game_meta) as usize;
+    let lower_is_better = utility.get_lower_is_better();
+    let mut best: Option<(Trunk, Float)> = None;
+    for utilities in get_utilities(utility, max_score) {
+        let trunk = get_objective_trunk(game_meta, Objective::ScoreUtility(Arc::new(utilities)));
+        let goal_value = utility.get_goal_value(&get_score_chances(&trunk));
+        let is_better = match &best {
+            Some((_, best_goal_value)) => {
+                if lower_is_better {
+                    goal_value < *best_goal_value
+                } else {
+                    goal_value > *best_goal_value
+                }
+            }
+            None => true,
+        };
+        if is_better {
+            best = Some((trunk, goal_value));
+        }
+    }
+    best.unwrap().0
+}
+
+/// A decision where playing for the goal differs from playing for another objective
+struct Difference {
+    /// The open tiles
+    tiles: Tiles,
+    /// The roll, None for the choice of how many dice to roll
+    roll: Option<Roll>,
+    /// What playing for the goal does
+    goal_choice: String,
+    /// What playing for the other objective does
+    other_choice: String,
+    /// Chance of the decision coming up in a game played for the goal
+    chance: Float,
+}
+
+/// Returns every decision where playing for the goal does worse by another solved objective, most common first
+fn get_differences(goal_trunk: &Trunk, other_trunk: &Trunk) -> Vec<Difference> {
+    let game_meta = &goal_trunk.game_meta;
+    let mut differences = Vec::new();
+    for (tiles, reach_chance) in get_reach_chances(goal_trunk) {
+        if tiles.is_empty() || reach_chance <= 0. {
+            continue;
+        }
+        let goal_states_hm = get_solved_best_states(&tiles, game_meta, &goal_trunk.game_db);
+        let other_states_hm =
+            get_solved_best_states(&tiles, &other_trunk.game_meta, &other_trunk.game_db);
+
+        let (goal_single, goal_multi) = get_dice_win_chances(&tiles, &goal_states_hm, game_meta);
+        let goal_use_single = goal_single > goal_multi;
+        let (other_single, other_multi) =
+            get_dice_win_chances(&tiles, &other_states_hm, &other_trunk.game_meta);
+        let other_use_single = other_single > other_multi + TIE_TOLERANCE;
+        let (goal_dice_value, best_dice_value) = if goal_use_single {
+            (other_single, other_single.max(other_multi))
+        } else {
+            (other_multi, other_single.max(other_multi))
+        };
+        if get_single_legality(&tiles, &game_meta.die_max)
+            && goal_dice_value < best_dice_value - TIE_TOLERANCE
+        {
+            let get_dice_string = |use_single: bool| {
+                if use_single {
+                    "roll one die"
+                } else {
+                    "roll all dice"
+                }
+                .to_string()
+            };
+            differences.push(Difference {
+                tiles: tiles.clone(),
+                roll: None,
+                goal_choice: get_dice_string(goal_use_single),
+                other_choice: get_dice_string(other_use_single),
+                chance: reach_chance,
+            });
+        }
+
+        let roll_probs = if goal_use_single {
+            &game_meta.roll_probs_single
+        } else {
+            &game_meta.roll_probs_multi
+        };
+        for (roll, roll_prob) in roll_probs {
+            let (goal_state, other_state) =
+                match (goal_states_hm.get(roll), other_states_hm.get(roll)) {
+                    (Some((goal_state, _)), Some((other_state, _))) => (goal_state, other_state),
+                    _ => continue,
+                };
+            let goal_value = other_trunk.game_db[goal_state];
+            let other_value = other_trunk.game_db[other_state];
+            if goal_value < other_value - TIE_TOLERANCE {
+                let get_flip_string = |state: &Tiles| {
+                    format!(
+                        "flip {}",
+                        get_tiles_string(&get_flipped_tiles(&tiles, state))
+                    )
+                };
+                differences.push(Difference {
+                    tiles: tiles.clone(),
+                    roll: Some(roll.clone()),
+                    goal_choice: get_flip_string(goal_state),
+                    other_choice: get_flip_string(other_state),
+                    chance: reach_chance * roll_prob,
+                });
+            }
+        }
+    }
+    differences.sort_by(|a, b| {
+        b.chance
+            .total_cmp(&a.chance)
+            .then_with(|| a.tiles.cmp(&b.tiles))
+            .then_with(|| a.roll.cmp(&b.roll))
+    });
+    differences
+}
+
+/// Returns a readable report of perfect play for a goal, and how it differs from playing to shut the box,
+/// or why not when the scores range too widely to solve for every threshold or mean the goal needs
+pub fn get_readable_utility_string(
+    game_meta: &GameMeta,
+    utility: &Utility,
+    row_cnt: usize,
+) -> Result<String, String> {
+    let max_score = get_score(&game_meta.tiles, game_meta) as usize;
+    let solve_cnt = utility.get_solve_cnt(max_score);
+    if solve_cnt > MAX_GOAL_SOLVES {
+        return Err(format!(
+            "Scores up to {} take {} solves for the {}, more than the limit of {}",
+            max_score,
+            solve_cnt,
+            utility.get_description(),
+            MAX_GOAL_SOLVES
+        ));
+    }
+    let goal_trunk = get_utility_trunk(game_meta, utility);
+    let win_trunk = get_objective_trunk(game_meta, Objective::WinChance);
+
+    let mut s = String::new();
+    s.push_str(&format!(
+        "Goal: {} ({} is better)\n",
+        utility.get_description(),
+        if utility.get_lower_is_better() {
+            "lower"
+        } else {
+            "higher"
+        }
+    ));
+    s.push_str("                         Goal  Win chance  Expected score\n");
+    for (name, trunk) in [
+        ("Playing for the goal", &goal_trunk),
+        ("Playing to shut", &win_trunk),
+    ] {
+        let score_chances = get_score_chances(trunk);
+        s.push_str(&format!(
+            "{:<20} {:>8.4} {:>10.4}% {:>15.4}\n",
+            name,
+            utility.get_goal_value(&score_chances),
+            score_chances[0] * 100.,
+            get_expected_score(&score_chances)
+        ));
+    }
+
+    let differences = get_differences(&goal_trunk, &win_trunk);
+    s.push_str(&get_readable_differences_string(
+        &differences,
+        "playing to shut the box",
+        row_cnt,
+    ));
+    Ok(s)
+}
+
+/// Returns a readable list of the most common decisions that differ from playing for another objective
+fn get_readable_differences_string(
+    differences: &[Difference],
+    other_name: &str,
+    row_cnt: usize,
+) -> String {
+    let mut s = String::new();
+    let per_game: Float = differences.iter().map(|difference| difference.chance).sum();
+    s.push_str(&format!(
+        "Decisions that differ from {}: {} ({:.4} per game)\n",
+        other_name,
+        differences.len(),
+        per_game
+    ));
+    for difference in differences.iter().take(row_cnt) {
+        let roll = match &difference.roll {
+            Some(roll) => format!(", roll {}", get_tiles_string(roll)),
+            None => String::new(),
+        };
+        s.push_str(&format!(
+            "  Tiles {}{}: {} instead of {} ({:.2}% of games)\n",
+            get_tiles_string(&difference.tiles),
+            roll,
+            difference.goal_choice,
+            difference.other_choice,
+            difference.chance * 100.
+        ));
+    }
+    s
+}
+
+/// Returns the expected score of a solved game under a scoring, when playing perfectly from the start
+fn get_expected_scoring_score(trunk: &Trunk, scoring: Scoring) -> Float {
+    get_end_chances(trunk)
+        .iter()
+        .map(|(tiles, end_chance)| end_chance * get_scoring_score(tiles, scoring, &trunk.game_meta))
+        .sum()
+}
+
+/// Returns the chance of a solved game ending with each number of tiles left, indexed by tile count
+fn get_tile_count_chances(trunk: &Trunk) -> Vec<Float> {
+    let mut tile_count_chances = vec![0.; trunk.game_meta.tiles.len() + 1];
+    for (tiles, end_chance) in get_end_chances(trunk) {
+        tile_count_chances[tiles.len()] += end_chance;
+    }
+    tile_count_chances
+}
+
+/// Returns a readable report of perfect play for the lowest expected score under a scoring,
+/// and how it differs from playing for the lowest pip sum, or from playing to shut the box when scoring by pip sum
+pub fn get_readable_scoring_string(
+    game_meta: &GameMeta,
+    scoring: Scoring,
+    row_cnt: usize,
+) -> String {
+    let scoring_trunk = get_objective_trunk(game_meta, Objective::LowScore(scoring));
+    let pip_sum_trunk = get_objective_trunk(game_meta, Objective::LowScore(Scoring::PipSum));
+    let win_trunk = get_objective_trunk(game_meta, Objective::WinChance);
+
+    let mut s = String::new();
+    s.push_str(&format!(
+        "Goal: lowest expected score, scoring {}\n",
+        scoring.get_description()
+    ));
+    s.push_str("                          Expected score  Win chance  Expected pip sum\n");
+    for (name, trunk) in [
+        ("Playing for this score", &scoring_trunk),
+        ("Playing for pip sum", &pip_sum_trunk),
+        ("Playing to shut", &win_trunk),
+    ] {
+        let score_chances = get_score_chances(trunk);
+        s.push_str(&format!(
+            "{:<22} {:>18.4} {:>10.4}% {:>17.4}\n",
+            name,
+            get_expected_scoring_score(trunk, scoring),
+            score_chances[0] * 100.,
+            get_expected_score(&score_chances)
+        ));
+    }
+
+    s.push_str("Tiles left  Playing for this score  Playing for pip sum  Playing to shut\n");
+    let scoring_chances = get_tile_count_chances(&scoring_trunk);
+    let pip_sum_chances = get_tile_count_chances(&pip_sum_trunk);
+    let win_chances = get_tile_count_chances(&win_trunk);
+    for (tile_cnt, ((scoring_chance, pip_sum_chance), win_chance)) in scoring_chances
+        .iter()
+        .zip(&pip_sum_chances)
+        .zip(&win_chances)
+        .enumerate()
+    {
+        s.push_str(&format!(
+            "{:>10}  {:>21.4}%  {:>18.4}%  {:>14.4}%\n",
+            tile_cnt,
+            scoring_chance * 100.,
+            pip_sum_chance * 100.,
+            win_chance * 100.
+        ));
+    }
+
+    let (other_trunk, other_name) = if scoring == Scoring::PipSum {
+        (&win_trunk, "playing to shut the box")
+    } else {
+        (&pip_sum_trunk, "playing for the lowest pip sum")
+    };
+    let differences = get_differences(&scoring_trunk, other_trunk);
+    s.push_str(&get_readable_differences_string(
+        &differences,
+        other_name,
+        row_cnt,
+    ));
+    s
+}
+
+/// Returns the Utility named on the command line, or why it isn't one.
+/// Accepts score, payout:SCORE=PAYOUT,..., cvar:FRACTION, or variance:WEIGHT
+pub fn get_utility(utility: &str) -> Result<Utility, String> {
+    let (name, params) = utility.split_once(':').unwrap_or((utility, ""));
+    let parsed = match name {
+        "score" => Some(Utility::LowScore),
+        "payout" => params
+            .split(',')
+            .map(|entry| {
+                let (score, payout) = entry.split_once('=')?;
+                Some((score.trim().parse().ok()?, payout.trim().parse().ok()?))
+            })
+            .collect::<Option<Vec<(Uns, Float)>>>()
+            .map(Utility::Payout),
+        "cvar" => params
+            .parse()
+            .ok()
+            .filter(|alpha: &Float| *alpha > 0. && *alpha <= 1.)
+            .map(Utility::Cvar),
+        "variance" => params
+            .parse()
+            .ok()
+            .filter(|lambda: &Float| *lambda >= 0.)
+            .map(Utility::MeanVariance),
+        _ => None,
+    };
+    parsed.ok_or_else(|| {
+        format!(
+            "Unknown utility \"{}\", expected score, payout:SCORE=PAYOUT,..., cvar:FRACTION (0 to 1], or variance:WEIGHT",
+            utility
+        )
+    })
+}
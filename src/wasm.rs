@@ -0,0 +1,18 @@
+use crate::json_api::JsonApi;
+use std::cell::RefCell;
+use wasm_bindgen::prelude::*;
+
+/// The most game states to solve for a single request, each takes roughly 100 bytes of the browser's memory
+const MAX_STATES: u64 = 1 << 20;
+
+thread_local! {
+    /// Solved games kept between calls, the page runs the module on a single thread
+    static JSON_API: RefCell<JsonApi> = RefCell::new(JsonApi::new(MAX_STATES));
+}
+
+/// Answers a request like the JSON API server does, ex: answer("/move", '{"tiles": [1, 4, 7], "roll": [5, 3]}').
+/// Returns the JSON body of the response, which holds an error field if the request can't be answered.
+#[wasm_bindgen]
+pub fn answer(path: &str, body: &str) -> String {
+    JSON_API.with(|json_api| json_api.borrow_mut().answer(path, body).1)
+}
@@ -0,0 +1,126 @@
+use shut_the_box_rust::cheat_sheet::write_cheat_sheet;
+use shut_the_box_rust::query::{get_policy, BestMove};
+use shut_the_box_rust::{
+    get_game_meta, get_mulligan_game_meta, get_scoring_game_meta, get_trunk, InitData, Scoring,
+    Trunk,
+};
+use std::collections::HashMap;
+
+/// Writes the Markdown cheat sheet of a solved game listing every board,
+/// and returns its table as the column names and each row's cells keyed by column name
+fn get_cheat_sheet_table(trunk: &Trunk, name: &str) -> (Vec<String>, Vec<HashMap<String, String>>) {
+    let path = std::env::temp_dir().join(format!("{}_{}.md", name, std::process::id()));
+    write_cheat_sheet(trunk, path.to_str().unwrap(), usize::MAX).unwrap();
+    let markdown = std::fs::read_to_string(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    let mut lines = markdown.lines().filter(|line| line.starts_with('|'));
+    let get_cells = |line: &str| -> Vec<String> {
+        line.trim_matches('|')
+            .split('|')
+            .map(|cell| cell.trim().to_string())
+            .collect()
+    };
+    let names = get_cells(lines.next().unwrap());
+    let rows = lines
+        .skip(1)
+        .map(|line| names.iter().cloned().zip(get_cells(line)).collect())
+        .collect();
+    (names, rows)
+}
+
+/// Checks every cell of a small cheat sheet against the solved policy
+fn check_cells_match_policy(trunk: &Trunk, name: &str) {
+    let (names, rows) = get_cheat_sheet_table(trunk, name);
+    // every column shows at least one move
+    for name in &names {
+        assert!(rows.iter().any(|row| !row[name].is_empty()), "{}", name);
+    }
+    let policy = get_policy(trunk);
+    for row in &rows {
+        let tiles_string = &row["Open tiles"];
+        let state_policy = policy
+            .iter()
+            .find(|state| {
+                let tiles: Vec<String> = state.tiles.iter().map(|tile| tile.to_string()).collect();
+                tiles.join(" ") == *tiles_string
+            })
+            .unwrap();
+        let dice = if state_policy.stops {
+            "stop"
+        } else if state_policy.use_single {
+            "one"
+        } else {
+            "all"
+        };
+        assert_eq!(row["Dice"], dice, "{}", tiles_string);
+        // with the dice total alone deciding the move, each column is a total
+        for name in names.iter().skip(4) {
+            let total: u32 = name.parse().unwrap();
+            let cell = match state_policy
+                .choices
+                .iter()
+                .find(|choice| choice.roll == [total])
+            {
+                Some(choice) => match &choice.best_move {
+                    BestMove::Flip(flip) => flip
+                        .iter()
+                        .map(|tile| tile.to_string())
+                        .collect::<Vec<String>>()
+                        .join("+"),
+                    BestMove::Stuck => "stuck".to_string(),
+                    best_move => panic!("{:?} can't happen under these rules", best_move),
+                },
+                None => String::new(),
+            };
+            assert_eq!(row[name], cell, "{} rolling {}", tiles_string, total);
+        }
+    }
+}
+
+/// Returns the solved game of two dice numbered 1 to 3 on the given tiles
+fn get_small_trunk(start_tiles: Vec<u32>) -> Trunk {
+    let init_data = InitData {
+        die_vals: (1..=3).collect(),
+        start_tiles,
+        ..InitData::default()
+    };
+    get_trunk(get_mulligan_game_meta(get_game_meta(&init_data).unwrap()))
+}
+
+#[test]
+fn cells_match_the_policy() {
+    let trunk = get_small_trunk((1..=5).collect());
+    let (names, rows) = get_cheat_sheet_table(&trunk, "cells_match_the_policy");
+    // two dice can't flip a lone 1, but one die does a third of the time
+    let row = rows.iter().find(|row| row["Open tiles"] == "1").unwrap();
+    assert_eq!(row["Dice"], "one");
+    assert_eq!(names[4..], ["1", "2", "3", "4", "5", "6"]);
+    check_cells_match_policy(&trunk, "cells_match_the_policy");
+}
+
+#[test]
+fn unrolled_rolls_get_no_column() {
+    // one die is never legal with a tile above 3 open, so there's no column for a 1
+    let trunk = get_small_trunk((4..=6).collect());
+    let (names, _) = get_cheat_sheet_table(&trunk, "unrolled_rolls_get_no_column");
+    assert_eq!(names[4..], ["2", "3", "4", "5", "6"]);
+    check_cells_match_policy(&trunk, "unrolled_rolls_get_no_column");
+}
+
+#[test]
+fn stopping_rows_say_stop() {
+    // two 1 to 2 dice can't shut a lone 1, so playing for a low score stops there instead of going bust
+    let init_data = InitData {
+        die_vals: (1..=2).collect(),
+        start_tiles: (1..=4).collect(),
+        stop_early: true,
+        ..InitData::default()
+    };
+    let game_meta = get_scoring_game_meta(get_game_meta(&init_data).unwrap(), Scoring::PipSum);
+    let trunk = get_trunk(get_mulligan_game_meta(game_meta));
+    let (_, rows) = get_cheat_sheet_table(&trunk, "stopping_rows_say_stop");
+    let row = rows.iter().find(|row| row["Open tiles"] == "1");
+    assert_eq!(row.unwrap()["Dice"], "stop");
+    check_cells_match_policy(&trunk, "stopping_rows_say_stop");
+}
@@ -0,0 +1,562 @@
+use proptest::prelude::*;
+use shut_the_box_rust::move_gen::MoveGen;
+use shut_the_box_rust::{
+    depth_solve, get_game_meta, get_max_state_cnt, get_mulligan_game_meta,
+    get_mulligan_game_meta_from, get_scoring_game_meta, get_state_cnt, get_trunk, naive_solve,
+    par_solve, try_get_solvable_game_meta, value_solve, BoxMode, Combiner, DoublesRule, Float,
+    InitData, RemovalLimits, RemovalRule, Roll, Scoring, Tiles, Uns,
+};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+/// Solvers agree on a game state's win chance when they differ by less than this
+const TOLERANCE: Float = 1e-9;
+
+/// Returns rules small enough for every algorithm to solve quickly, mixing every kind of house rule,
+/// and whether to solve them for the lowest expected pip score instead of the win chance
+fn small_rules() -> impl Strategy<Value = (InitData, bool)> {
+    let dice = (2..=6 as Uns, 1..=3 as Uns);
+    let tiles = (
+        prop::collection::vec(1..=10 as Uns, 1..=6),
+        prop::collection::hash_map(1..=10 as Uns, 0..=20 as Uns, 0..=3),
+        prop_oneof![Just(BoxMode::Shut), Just(BoxMode::OpenAndShut)],
+    );
+    let removal = (
+        prop_oneof![
+            Just(RemovalRule::SumOnly),
+            Just(RemovalRule::IndividualOnly),
+            Just(RemovalRule::SumOrIndividual),
+        ],
+        0..=2 as Uns,
+        0..=2 as Uns,
+        any::<bool>(),
+        any::<bool>(),
+        prop::option::of(1..=6 as Uns),
+    );
+    let bonuses = (
+        prop_oneof![
+            Just(Combiner::Sum),
+            Just(Combiner::Product),
+            Just(Combiner::Difference),
+            Just(Combiner::Max),
+        ],
+        prop_oneof![
+            Just(DoublesRule::NoBonus),
+            Just(DoublesRule::Reroll),
+            Just(DoublesRule::SkipIfStuck),
+            Just(DoublesRule::DoubleCount),
+        ],
+        any::<bool>(),
+        0..=1 as Uns,
+    );
+    (dice, tiles, removal, bonuses, any::<bool>()).prop_map(
+        |(
+            (die_max, die_cnt),
+            (mut start_tiles, tile_points, box_mode),
+            (removal_rule, max_remove, exact_count, adjacent, include_highest, one_above),
+            (combiner, doubles_rule, stop_early, mulligans),
+            low_score,
+        )| {
+            start_tiles.sort_unstable();
+            let init_data = InitData {
+                die_vals: (1..=die_max).collect(),
+                die_cnt,
+                start_tiles,
+                tile_points,
+                max_remove,
+                box_mode,
+                combiner,
+                removal_rule,
+                removal_limits: RemovalLimits {
+                    exact_count,
+                    adjacent,
+                    include_highest,
+                    one_above,
+                },
+                doubles_rule,
+                stop_early,
+                mulligans,
+                ..InitData::default()
+            };
+            (init_data, low_score)
+        },
+    )
+}
+
+/// Returns the sorted faces of every way the dice can land and the chance of each, found by listing every way
+fn get_dice_chances(die_vals: &[Uns], die_cnt: Uns) -> Vec<(Vec<Uns>, Float)> {
+    let mut outcomes: Vec<Vec<Uns>> = vec![Vec::new()];
+    for _ in 0..die_cnt {
+        outcomes = outcomes
+            .iter()
+            .flat_map(|dice| {
+                die_vals
+                    .iter()
+                    .map(move |face| [&dice[..], &[*face]].concat())
+            })
+            .collect();
+    }
+    let mut dice_chances: BTreeMap<Vec<Uns>, Float> = BTreeMap::new();
+    for mut dice in outcomes.iter().cloned() {
+        dice.sort_unstable();
+        *dice_chances.entry(dice).or_insert(0.) += 1. / outcomes.len() as Float;
+    }
+    dice_chances.into_iter().collect()
+}
+
+/// Returns whether every die shows the same face, with more than one die
+fn get_is_doubles(dice: &[Uns]) -> bool {
+    dice.len() > 1 && dice.iter().all(|die| *die == dice[0])
+}
+
+/// Returns the total the dice count as under the rules, worked out from the faces
+fn get_dice_total(init_data: &InitData, dice: &[Uns]) -> Uns {
+    let highest = dice[dice.len() - 1];
+    let total = match init_data.combiner {
+        Combiner::Sum => dice.iter().sum(),
+        Combiner::Product => dice.iter().product(),
+        // a lone die has nothing to take away from its face
+        Combiner::Difference if dice.len() > 1 => highest - dice[0],
+        Combiner::Difference => highest,
+        Combiner::Max => highest,
+    };
+    if init_data.doubles_rule == DoublesRule::DoubleCount && get_is_doubles(dice) {
+        total * 2
+    } else {
+        total
+    }
+}
+
+/// Returns the roll the move generator is asked about for the dice, their faces when the rules need them
+fn get_dice_roll(init_data: &InitData, dice: &[Uns]) -> Roll {
+    let keeps_dice = init_data.removal_rule != RemovalRule::SumOnly
+        || init_data.doubles_rule != DoublesRule::NoBonus;
+    if keeps_dice {
+        dice.to_vec()
+    } else {
+        vec![get_dice_total(init_data, dice)]
+    }
+}
+
+/// Returns every game state the dice lead to from the open tiles, found by trying every set of open tiles
+/// against the rules as written, without the move generator
+fn get_brute_force_next_states(
+    init_data: &InitData,
+    tiles: &Tiles,
+    dice: &[Uns],
+) -> BTreeSet<Tiles> {
+    let total = get_dice_total(init_data, dice);
+    let mut faces = dice.to_vec();
+    faces.dedup();
+    let limits = &init_data.removal_limits;
+    let mut next_states = BTreeSet::new();
+    for subset in 1..1u32 << tiles.len() {
+        let (flip, next_state): (Tiles, Tiles) = (
+            (0..tiles.len())
+                .filter(|id| subset & (1 << id) != 0)
+                .map(|id| tiles[id])
+                .collect(),
+            (0..tiles.len())
+                .filter(|id| subset & (1 << id) == 0)
+                .map(|id| tiles[id])
+                .collect(),
+        );
+        let sum_flip = init_data.removal_rule != RemovalRule::IndividualOnly
+            && flip.iter().sum::<Uns>() == total
+            && (init_data.max_remove == 0 || flip.len() <= init_data.max_remove as usize);
+        let individual_flip = init_data.removal_rule != RemovalRule::SumOnly && flip == faces;
+        let exact = limits.exact_count == 0 || flip.len() == limits.exact_count as usize;
+        let adjacent = !limits.adjacent
+            || flip.windows(2).all(|pair| {
+                !init_data
+                    .start_tiles
+                    .iter()
+                    .any(|tile| pair[0] < *tile && *tile < pair[1])
+            });
+        let highest = !limits.include_highest || flip.last() == tiles.last();
+        let one_above = limits
+            .one_above
+            .is_none_or(|low| flip.iter().filter(|tile| **tile > low).count() <= 1);
+        if (sum_flip || individual_flip) && exact && adjacent && highest && one_above {
+            next_states.insert(next_state);
+        }
+    }
+    if init_data.box_mode == BoxMode::OpenAndShut && next_states.is_empty() {
+        let mut shut_tiles = init_data.start_tiles.clone();
+        for tile in tiles {
+            shut_tiles.remove(shut_tiles.iter().position(|x| x == tile).unwrap());
+        }
+        for tile in shut_tiles {
+            let sum_reopen = init_data.removal_rule != RemovalRule::IndividualOnly && tile == total;
+            let individual_reopen =
+                init_data.removal_rule != RemovalRule::SumOnly && faces == [tile];
+            if sum_reopen || individual_reopen {
+                let mut next_state = tiles.clone();
+                next_state.push(tile);
+                next_state.sort_unstable();
+                next_states.insert(next_state);
+            }
+        }
+    }
+    next_states
+}
+
+/// The sorted faces of a roll, its chance, and the game states it leads to
+type DiceMoves = (Vec<Uns>, Float, BTreeSet<Tiles>);
+
+/// Returns every distinct sorted set of the starting tiles
+fn get_all_states(start_tiles: &Tiles) -> Vec<Tiles> {
+    let mut states: Vec<Tiles> = (0..1u32 << start_tiles.len())
+        .map(|subset| {
+            (0..start_tiles.len())
+                .filter(|id| subset & (1 << id) != 0)
+                .map(|id| start_tiles[id])
+                .collect()
+        })
+        .collect();
+    states.sort_unstable();
+    states.dedup();
+    states
+}
+
+/// Returns the value of ending a game with the tiles: whether it's won, or minus the pip score
+fn get_reference_end_value(init_data: &InitData, tiles: &Tiles, low_score: bool) -> Float {
+    if low_score {
+        -tiles
+            .iter()
+            .map(|tile| *init_data.tile_points.get(tile).unwrap_or(tile) as Float)
+            .sum::<Float>()
+    } else if tiles.is_empty() {
+        1.
+    } else {
+        0.
+    }
+}
+
+/// Returns the value of every game state under perfect play, solved by value iteration over brute force moves.
+/// Written from the rules alone, sharing no code with the library's solvers or move generator.
+fn get_reference_values(init_data: &InitData, low_score: bool) -> HashMap<Tiles, Float> {
+    let states = get_all_states(&init_data.start_tiles);
+    let die_max = *init_data.die_vals.iter().max().unwrap();
+    let single_chances = get_dice_chances(&init_data.die_vals, 1);
+    let multi_chances = get_dice_chances(&init_data.die_vals, init_data.die_cnt);
+    let moves: Vec<Vec<Vec<DiceMoves>>> = states
+        .iter()
+        .map(|tiles| {
+            [&single_chances, &multi_chances]
+                .iter()
+                .map(|dice_chances| {
+                    dice_chances
+                        .iter()
+                        .map(|(dice, chance)| {
+                            let next_states = get_brute_force_next_states(init_data, tiles, dice);
+                            (dice.clone(), *chance, next_states)
+                        })
+                        .collect()
+                })
+                .collect()
+        })
+        .collect();
+    let end_values: Vec<Float> = states
+        .iter()
+        .map(|tiles| get_reference_end_value(init_data, tiles, low_score))
+        .collect();
+    let bust_value = get_reference_end_value(init_data, &init_data.start_tiles, low_score);
+    let min_value = end_values.iter().copied().fold(Float::INFINITY, Float::min);
+
+    let mut mulligan_values: Option<HashMap<Tiles, Float>> = None;
+    for _ in 0..=init_data.mulligans {
+        let mut values: HashMap<Tiles, Float> = states
+            .iter()
+            .zip(&end_values)
+            .map(|(tiles, end_value)| {
+                let value = if tiles.is_empty() {
+                    *end_value
+                } else {
+                    min_value
+                };
+                (tiles.clone(), value)
+            })
+            .collect();
+        for _ in 0..100_000 {
+            let mut max_change: Float = 0.;
+            for (id, tiles) in states
+                .iter()
+                .enumerate()
+                .filter(|(_, tiles)| !tiles.is_empty())
+            {
+                let stuck_value = if init_data.stop_early {
+                    bust_value
+                } else {
+                    end_values[id]
+                };
+                let own_value = values[tiles];
+                let mulligan_value = mulligan_values.as_ref().map(|values| values[tiles]);
+                let mut value = Float::NEG_INFINITY;
+                for (dice_cnt_id, dice_moves) in moves[id].iter().enumerate() {
+                    if dice_cnt_id == 0 && *tiles.last().unwrap() > die_max {
+                        continue;
+                    }
+                    let mut dice_value = 0.;
+                    for (dice, chance, next_states) in dice_moves {
+                        let best = next_states
+                            .iter()
+                            .map(|next_state| values[next_state])
+                            .reduce(Float::max);
+                        let mut roll_value = match init_data.doubles_rule {
+                            DoublesRule::Reroll if get_is_doubles(dice) => {
+                                best.unwrap_or(stuck_value).max(own_value)
+                            }
+                            DoublesRule::SkipIfStuck if get_is_doubles(dice) => {
+                                best.unwrap_or(own_value)
+                            }
+                            _ => best.unwrap_or(stuck_value),
+                        };
+                        if let Some(mulligan_value) = mulligan_value {
+                            roll_value = roll_value.max(mulligan_value);
+                        }
+                        dice_value += chance * roll_value;
+                    }
+                    value = value.max(dice_value);
+                }
+                if init_data.stop_early {
+                    value = value.max(end_values[id]);
+                }
+                max_change = max_change.max((value - own_value).abs());
+                values.insert(tiles.clone(), value);
+            }
+            if max_change < 1e-13 {
+                break;
+            }
+        }
+        mulligan_values = Some(values);
+    }
+    mulligan_values.unwrap()
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(64))]
+
+    #[test]
+    fn moves_match_brute_force((init_data, _) in small_rules()) {
+        let game_meta = get_game_meta(&init_data).unwrap();
+        let move_gen = MoveGen::new(&game_meta).unwrap();
+        // tiles no roll can flip stay open in every game state the move generator is asked about
+        let mut to_visit = vec![init_data.start_tiles.clone()];
+        let mut visited = BTreeSet::new();
+        while let Some(tiles) = to_visit.pop() {
+            if !visited.insert(tiles.clone()) {
+                continue;
+            }
+            for die_cnt in [1, init_data.die_cnt] {
+                for (dice, _) in get_dice_chances(&init_data.die_vals, die_cnt) {
+                    let roll = get_dice_roll(&init_data, &dice);
+                    let next_states: BTreeSet<Tiles> =
+                        move_gen.get_next_states(&tiles, &roll, &game_meta).into_iter().collect();
+                    let brute_force_next_states = get_brute_force_next_states(&init_data, &tiles, &dice);
+                    prop_assert_eq!(&next_states, &brute_force_next_states, "{:?} rolling {:?}", tiles, dice);
+                    to_visit.extend(next_states);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn algorithms_agree_on_every_state((init_data, low_score) in small_rules()) {
+        let mut game_meta = get_game_meta(&init_data).unwrap();
+        if low_score {
+            game_meta = get_scoring_game_meta(game_meta, Scoring::PipSum);
+        }
+        let game_meta = get_mulligan_game_meta(game_meta);
+        let tiles: Tiles = init_data.start_tiles.clone();
+        let reference_values = get_reference_values(&init_data, low_score);
+        let value_db = value_solve(&game_meta);
+        if init_data.box_mode == BoxMode::OpenAndShut {
+            // game states can repeat, which only value iteration solves
+            for (state, value) in &value_db {
+                prop_assert!((reference_values[state] - value).abs() < TOLERANCE, "value_solve at {:?}", state);
+            }
+            return Ok(());
+        }
+
+        let mut depth_db = HashMap::new();
+        depth_solve(tiles.clone(), &game_meta, &mut depth_db);
+        let par_db = par_solve(tiles, game_meta.clone());
+        // the naive solver can't roll again from the same game state
+        let rolls_again = matches!(
+            init_data.doubles_rule,
+            DoublesRule::Reroll | DoublesRule::SkipIfStuck
+        );
+
+        prop_assert!(get_max_state_cnt(&init_data) >= get_state_cnt(&game_meta));
+        prop_assert_eq!(depth_db.len(), par_db.len());
+        for (state, win_chance) in &depth_db {
+            prop_assert!((reference_values[state] - win_chance).abs() < TOLERANCE, "depth_solve at {:?}", state);
+            prop_assert!((par_db[state] - win_chance).abs() < TOLERANCE, "par_solve at {:?}", state);
+            prop_assert!((value_db[state] - win_chance).abs() < TOLERANCE, "value_solve at {:?}", state);
+            if !rolls_again {
+                let (naive_win_chance, _) = naive_solve(state.clone(), &game_meta);
+                prop_assert!((naive_win_chance - win_chance).abs() < TOLERANCE, "naive_solve at {:?}", state);
+            }
+        }
+    }
+}
+
+/// Returns the win chance of the starting tiles, checking the memoized algorithms agree.
+/// Naive solving is left to the small rules above, it takes minutes on the classic board without optimizations.
+fn get_win_chance(init_data: &InitData) -> Float {
+    let game_meta = get_mulligan_game_meta(get_game_meta(init_data).unwrap());
+    let tiles: Tiles = init_data.start_tiles.clone();
+    let win_chance = depth_solve(tiles.clone(), &game_meta, &mut HashMap::new());
+    let win_chances = [
+        par_solve(tiles.clone(), game_meta.clone())[&tiles],
+        value_solve(&game_meta)[&tiles],
+    ];
+    for other_win_chance in win_chances {
+        assert!((other_win_chance - win_chance).abs() < TOLERANCE);
+    }
+    win_chance
+}
+
+#[test]
+fn classic_win_chance_snapshot() {
+    // the reference solver above works the classic win chance out from the rules alone,
+    // sharing no moves or solving with the library, so the pinned value isn't only a snapshot of itself
+    let init_data = InitData::default();
+    let win_chance = get_win_chance(&init_data);
+    let reference_win_chance = get_reference_values(&init_data, false)[&init_data.start_tiles];
+    assert!((win_chance - reference_win_chance).abs() < TOLERANCE);
+    assert!((win_chance - 0.097614).abs() < 5e-7, "{}", win_chance);
+}
+
+#[test]
+fn single_die_only_when_legal() {
+    // 7 can't be flipped with one die, so every algorithm must roll both dice from 1 and 7
+    let init_data = InitData {
+        start_tiles: vec![1, 7],
+        ..InitData::default()
+    };
+    let win_chance = get_win_chance(&init_data);
+    let game_meta = get_game_meta(&init_data).unwrap();
+    let (naive_win_chance, _) = naive_solve(init_data.start_tiles.clone(), &game_meta);
+    // an 8 wins outright, a 7 leaves the 1 for a single die
+    let expected = 5. / 36. + 6. / 36. * (1. / 6.);
+    assert!(
+        (win_chance - expected).abs() < TOLERANCE,
+        "{} vs {}",
+        win_chance,
+        expected
+    );
+    assert!(
+        (naive_win_chance - expected).abs() < TOLERANCE,
+        "{} vs {}",
+        naive_win_chance,
+        expected
+    );
+}
+
+#[test]
+fn totals_past_the_int_range_are_rejected() {
+    let init_data = |die_vals| InitData {
+        die_vals,
+        doubles_rule: DoublesRule::DoubleCount,
+        ..InitData::default()
+    };
+    assert!(try_get_solvable_game_meta(&init_data(vec![1, 3_000_000_000])).is_err());
+    // double 1,000,000,000s count as 4,000,000,000, just under the limit
+    assert!(try_get_solvable_game_meta(&init_data(vec![1, 1_000_000_000])).is_ok());
+}
+
+#[test]
+fn too_many_flippable_tiles_are_rejected() {
+    let init_data = |tile_cnt| InitData {
+        die_vals: (1..=tile_cnt).collect(),
+        die_cnt: 1,
+        start_tiles: (1..=tile_cnt).collect(),
+        max_remove: 1,
+        ..InitData::default()
+    };
+    assert!(get_game_meta(&init_data(64)).is_ok());
+    assert!(get_game_meta(&init_data(65)).is_err());
+}
+
+#[test]
+fn removal_limits_leave_reopening_alone() {
+    // from 3 alone a 1 can't shut anything, so it re-opens the 1,
+    // even though 2 is the highest shut tile and flips must move two tiles
+    let game_meta = get_game_meta(&InitData {
+        die_vals: vec![1, 2, 3],
+        die_cnt: 1,
+        start_tiles: vec![1, 2, 3],
+        box_mode: BoxMode::OpenAndShut,
+        removal_limits: RemovalLimits {
+            exact_count: 2,
+            include_highest: true,
+            ..RemovalLimits::default()
+        },
+        ..InitData::default()
+    })
+    .unwrap();
+    let move_gen = MoveGen::new(&game_meta).unwrap();
+    assert_eq!(
+        move_gen.get_next_states(&vec![3], &vec![1], &game_meta),
+        vec![vec![1, 3]]
+    );
+    // 1 and 2 can go down together on a 3, so a 3 shuts them instead of re-opening the 3
+    assert_eq!(
+        move_gen.get_next_states(&vec![1, 2], &vec![3], &game_meta),
+        vec![Vec::<Uns>::new()]
+    );
+}
+
+#[test]
+fn state_limit_is_checked_before_setting_up_moves() {
+    // listing every way to flip tiles 1 to 40 for totals up to 200 would take minutes and run out of memory
+    let init_data = InitData {
+        die_vals: (1..=100).collect(),
+        start_tiles: (1..=40).collect(),
+        max_states: 1000,
+        ..InitData::default()
+    };
+    let start = std::time::Instant::now();
+    assert!(try_get_solvable_game_meta(&init_data).is_err());
+    assert!(start.elapsed().as_secs() < 5);
+}
+
+#[test]
+fn open_and_shut_win_chance() {
+    // one three-sided die on tiles 1 and 2: a 3 shuts both tiles but loses with one left,
+    // and a roll matching the shut tile re-opens it. So one = 1/3 + 1/3 * both
+    // and both = 1/3 + 2/3 * one, which solve to one = 4/7 and both = 5/7
+    let game_meta = get_game_meta(&InitData {
+        die_vals: vec![1, 2, 3],
+        die_cnt: 1,
+        start_tiles: vec![1, 2],
+        box_mode: BoxMode::OpenAndShut,
+        ..InitData::default()
+    })
+    .unwrap();
+    let value_db = value_solve(&game_meta);
+    assert!((value_db[&vec![1, 2]] - 5. / 7.).abs() < TOLERANCE);
+    assert!((value_db[&vec![1]] - 4. / 7.).abs() < TOLERANCE);
+    assert!((value_db[&vec![2]] - 4. / 7.).abs() < TOLERANCE);
+}
+
+#[test]
+fn mulligan_win_chances() {
+    // one two-sided die on tile 1: a 1 wins and a 2 is stuck, where a mulligan rolls again.
+    // So each mulligan halves the chance of losing: 1/2, 3/4, then 7/8
+    let game_meta = get_game_meta(&InitData {
+        die_vals: vec![1, 2],
+        die_cnt: 1,
+        start_tiles: vec![1],
+        mulligans: 2,
+        ..InitData::default()
+    })
+    .unwrap();
+    let (game_meta, win_chances) = get_mulligan_game_meta_from(game_meta, &vec![1]);
+    assert_eq!(win_chances.len(), 2);
+    assert!((win_chances[0] - 1. / 2.).abs() < TOLERANCE);
+    assert!((win_chances[1] - 3. / 4.).abs() < TOLERANCE);
+    let trunk = get_trunk(game_meta);
+    assert!((trunk.game_db[&vec![1]] - 7. / 8.).abs() < TOLERANCE);
+}
@@ -0,0 +1,100 @@
+use shut_the_box_rust::heuristics::get_readable_heuristics_string;
+use shut_the_box_rust::{
+    get_trunk, try_get_solvable_game_meta, DoublesRule, Float, InitData, RemovalRule,
+};
+
+/// Win chances read from a report differ by less than this many percentage points, its values having 4 decimals
+const REPORT_TOLERANCE: Float = 1e-4;
+
+/// Returns the perfect play win chance and each heuristic's win chance and description from a report, in percent
+fn get_report_win_chances(report: &str) -> (Float, Vec<(Float, String)>) {
+    let get_percent = |text: &str| text.trim_end_matches('%').parse::<Float>().unwrap();
+    let optimal_line = report
+        .lines()
+        .find(|line| line.starts_with("Perfect play win chance"))
+        .unwrap();
+    let optimal = get_percent(optimal_line.split_whitespace().last().unwrap());
+    let heuristics = report
+        .lines()
+        .filter(|line| line.starts_with("  "))
+        .map(|line| {
+            let (win_chance, rest) = line.trim_start().split_once(' ').unwrap();
+            let (_, description) = rest.split_once(") ").unwrap();
+            (get_percent(win_chance), description.to_string())
+        })
+        .collect();
+    (optimal, heuristics)
+}
+
+#[test]
+fn flipping_the_most_tiles_by_hand() {
+    // one die on 1 2 3 flips a single tile, except a 3 may flip 1 and 2 instead.
+    // Each lone tile is shut a third of the time, 1 2 wins 5/9, and 1 3 and 2 3 win 2/9.
+    // Flipping 1 and 2 on a 3 leaves a lone 3, so the first roll wins (2/9 + 2/9 + 1/3) / 3 = 7/27,
+    // while flipping the 3 leaves 1 2 and wins perfect play's (2/9 + 2/9 + 5/9) / 3 = 1/3
+    let trunk = get_trunk(
+        try_get_solvable_game_meta(&InitData {
+            die_vals: (1..=3).collect(),
+            die_cnt: 1,
+            start_tiles: (1..=3).collect(),
+            ..InitData::default()
+        })
+        .expect("the rules are solvable"),
+    );
+    let report = get_readable_heuristics_string(&trunk, usize::MAX);
+    let (optimal, heuristics) = get_report_win_chances(&report);
+    assert!(
+        (optimal - 100. / 3.).abs() < REPORT_TOLERANCE,
+        "{}",
+        optimal
+    );
+    let get_win_chance = |description: &str| {
+        heuristics
+            .iter()
+            .find(|(_, other_description)| other_description == description)
+            .unwrap()
+            .0
+    };
+    let most =
+        get_win_chance("Flip the most tiles, tie-break the highest tiles; never roll one die");
+    assert!((most - 700. / 27.).abs() < REPORT_TOLERANCE, "{}", most);
+    let fewest =
+        get_win_chance("Flip the fewest tiles, tie-break the highest tiles; never roll one die");
+    assert!((fewest - 100. / 3.).abs() < REPORT_TOLERANCE, "{}", fewest);
+}
+
+#[test]
+fn no_heuristic_beats_perfect_play() {
+    let rules = [
+        InitData::default(),
+        InitData {
+            doubles_rule: DoublesRule::SkipIfStuck,
+            ..InitData::default()
+        },
+        InitData {
+            removal_rule: RemovalRule::SumOrIndividual,
+            max_remove: 2,
+            ..InitData::default()
+        },
+        InitData {
+            mulligans: 1,
+            start_tiles: (1..=7).collect(),
+            ..InitData::default()
+        },
+    ];
+    for init_data in rules {
+        let trunk =
+            get_trunk(try_get_solvable_game_meta(&init_data).expect("the rules are solvable"));
+        let report = get_readable_heuristics_string(&trunk, usize::MAX);
+        let (optimal, heuristics) = get_report_win_chances(&report);
+        assert!(!heuristics.is_empty());
+        for (win_chance, description) in heuristics {
+            assert!(
+                win_chance <= optimal + REPORT_TOLERANCE,
+                "{} {}",
+                win_chance,
+                description
+            );
+        }
+    }
+}
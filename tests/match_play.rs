@@ -0,0 +1,173 @@
+use shut_the_box_rust::match_play::get_readable_match_string;
+use shut_the_box_rust::query::{get_policy, BestMove};
+use shut_the_box_rust::utility::{get_readable_utility_string, Utility};
+use shut_the_box_rust::{get_trunk, try_get_solvable_game_meta, Float, InitData, Tiles, Uns};
+use std::collections::BTreeMap;
+
+/// Win chances read from a match report differ by less than this many percentage points, its values having 2 decimals
+const REPORT_TOLERANCE: Float = 5e-3;
+
+/// Returns the number in percent after the colon of the report line starting with the given text
+fn get_reported_percent(report: &str, start: &str) -> Float {
+    let line = report.lines().find(|line| line.starts_with(start)).unwrap();
+    let (_, value) = line.rsplit_once(": ").unwrap();
+    value.trim_end_matches('%').parse().unwrap()
+}
+
+/// Returns the match win chance in percent of the report's table row for a match score
+fn get_row_percent(report: &str, scores: (usize, usize)) -> Float {
+    report
+        .lines()
+        .map(|line| line.split_whitespace().collect::<Vec<&str>>())
+        .find(|cells| {
+            cells.len() == 5 && cells[0].parse() == Ok(scores.0) && cells[1].parse() == Ok(scores.1)
+        })
+        .unwrap()[2]
+        .trim_end_matches('%')
+        .parse()
+        .unwrap()
+}
+
+#[test]
+fn tiny_match_in_closed_form() {
+    // a lone 1 against a two sided die scores 0 or 1 with even chances, and leaves no choice to make.
+    // Rounds both players shut change nothing, so from a match score the chance of winning is
+    // the chance of the other three rounds turning out well, divided by 3/4.
+    // From 1 to 1 only 0 to 1 wins and 1 to 1 ties: (1/4 + 1/8) / (3/4) = 1/2.
+    // From 1 to 0 only 0 to 1 leads on, to 1 to 1: (1/4 * 1/2) / (3/4) = 1/6, so 0 to 1 wins 5/6.
+    // From 0 to 0 the rounds lead to each of those: (5/6 + 1/6 + 1/2) / 4 / (3/4) = 1/2.
+    let game_meta = try_get_solvable_game_meta(&InitData {
+        die_vals: vec![1, 2],
+        die_cnt: 1,
+        start_tiles: vec![1],
+        match_limit: 2,
+        ..InitData::default()
+    })
+    .expect("the rules are solvable");
+    let report = get_readable_match_string(&game_meta);
+    let expected_percents = [
+        ((0, 0), 50.),
+        ((0, 1), 500. / 6.),
+        ((1, 0), 100. / 6.),
+        ((1, 1), 50.),
+    ];
+    for (scores, expected_percent) in expected_percents {
+        let percent = get_row_percent(&report, scores);
+        assert!(
+            (percent - expected_percent).abs() < REPORT_TOLERANCE,
+            "{:?} {}",
+            scores,
+            percent
+        );
+    }
+    for start in [
+        "Match win chance with both players playing to shut the box",
+        "Match win chance playing each round to win the match",
+    ] {
+        let percent = get_reported_percent(&report, start);
+        assert!((percent - 50.).abs() < REPORT_TOLERANCE, "{}", percent);
+    }
+}
+
+/// Returns the chance of each score when playing to shut the box with two dice, indexed by score,
+/// found by following the solved policy from the starting tiles
+fn get_shut_score_chances(init_data: &InitData) -> Vec<Float> {
+    let trunk = get_trunk(try_get_solvable_game_meta(init_data).expect("the rules are solvable"));
+    let policy = get_policy(&trunk);
+    let die_vals = &init_data.die_vals;
+    // the rules count only the dice total, so a roll is its total
+    let get_roll_chance = |use_single: bool, roll: &[Uns]| {
+        if use_single {
+            return 1. / die_vals.len() as Float;
+        }
+        let ways = die_vals
+            .iter()
+            .flat_map(|a| die_vals.iter().map(move |b| a + b))
+            .filter(|total| *total == roll[0])
+            .count();
+        ways as Float / (die_vals.len() * die_vals.len()) as Float
+    };
+    let mut score_chances = vec![0.; init_data.start_tiles.iter().sum::<Uns>() as usize + 1];
+    let mut reach_chances: BTreeMap<Tiles, Float> =
+        BTreeMap::from([(init_data.start_tiles.clone(), 1.)]);
+    // flipping only ever leaves fewer tiles, so the last board is never reached from one before it
+    while let Some((tiles, reach_chance)) = reach_chances.pop_last() {
+        let state_policy = policy.iter().find(|state| state.tiles == tiles).unwrap();
+        if tiles.is_empty() {
+            score_chances[0] += reach_chance;
+        }
+        for choice in &state_policy.choices {
+            match &choice.best_move {
+                BestMove::Flip(flip) => {
+                    let next_tiles: Tiles = tiles
+                        .iter()
+                        .copied()
+                        .filter(|tile| !flip.contains(tile))
+                        .collect();
+                    *reach_chances.entry(next_tiles).or_insert(0.) +=
+                        reach_chance * get_roll_chance(state_policy.use_single, &choice.roll);
+                }
+                BestMove::Stuck => {
+                    score_chances[tiles.iter().sum::<Uns>() as usize] +=
+                        reach_chance * get_roll_chance(state_policy.use_single, &choice.roll);
+                }
+                best_move => panic!("{:?} can't happen under these rules", best_move),
+            }
+        }
+    }
+    score_chances
+}
+
+#[test]
+fn limit_zero_is_a_single_round() {
+    // a single round is the single game played to beat the score of an opponent playing to shut the box
+    let init_data = InitData {
+        die_vals: (1..=3).collect(),
+        start_tiles: (1..=6).collect(),
+        ..InitData::default()
+    };
+    let opponent_score_chances = get_shut_score_chances(&init_data);
+    // a single round pays for beating the opponent's score and half for a tie
+    let payouts = (0..opponent_score_chances.len())
+        .map(|score| {
+            let higher: Float = opponent_score_chances[score + 1..].iter().sum();
+            (score as Uns, higher + opponent_score_chances[score] / 2.)
+        })
+        .collect();
+    let game_meta = try_get_solvable_game_meta(&init_data).expect("the rules are solvable");
+    let utility_report =
+        get_readable_utility_string(&game_meta, &Utility::Payout(payouts), 0).unwrap();
+    let goal_line = utility_report
+        .lines()
+        .find(|line| line.starts_with("Playing for the goal"))
+        .unwrap();
+    let goal_value: Float = goal_line
+        .split_whitespace()
+        .nth(4)
+        .unwrap()
+        .parse()
+        .unwrap();
+
+    let report = get_readable_match_string(&game_meta);
+    let fixed_percent = get_reported_percent(
+        &report,
+        "Match win chance with both players playing to shut the box",
+    );
+    assert!(
+        (fixed_percent - 50.).abs() < REPORT_TOLERANCE,
+        "{}",
+        fixed_percent
+    );
+    let adapted_percent = get_reported_percent(
+        &report,
+        "Match win chance playing each round to win the match",
+    );
+    // playing to beat the score wins more than the half that playing to shut the box does
+    assert!(adapted_percent > 50.1, "{}", adapted_percent);
+    assert!(
+        (adapted_percent - goal_value * 100.).abs() < REPORT_TOLERANCE,
+        "{} {}",
+        adapted_percent,
+        goal_value
+    );
+}
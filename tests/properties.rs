@@ -0,0 +1,114 @@
+use proptest::prelude::*;
+use shut_the_box_rust::{
+    get_roll_probs, get_tile_removal_possibilities, Combiner, Roll, Tiles, Uns,
+};
+use std::collections::{BTreeSet, HashMap};
+
+/// Returns every distinct sorted combination of the tiles summing to a target, found by brute force
+fn get_brute_force_removals(tiles: &Tiles, target: Uns, removal_max: Uns) -> BTreeSet<Tiles> {
+    let mut removals = BTreeSet::new();
+    for subset in 0..1u32 << tiles.len() {
+        let removal: Tiles = (0..tiles.len())
+            .filter(|id| subset & (1 << id) != 0)
+            .map(|id| tiles[id])
+            .collect();
+        let in_limit = removal_max == 0 || removal.len() <= removal_max as usize;
+        if !removal.is_empty() && in_limit && removal.iter().sum::<Uns>() == target {
+            removals.insert(removal);
+        }
+    }
+    removals
+}
+
+/// Returns the chance of every roll found by listing every way the dice can land, each as likely
+fn get_enumerated_roll_probs(
+    die_vals: &[Uns],
+    die_cnt: Uns,
+    keep_dice: bool,
+    combiner: Combiner,
+) -> HashMap<Roll, f64> {
+    let mut outcomes: Vec<Roll> = vec![Vec::new()];
+    for _ in 0..die_cnt {
+        outcomes = outcomes
+            .iter()
+            .flat_map(|dice| {
+                die_vals
+                    .iter()
+                    .map(move |face| [&dice[..], &[*face]].concat())
+            })
+            .collect();
+    }
+    let mut roll_probs = HashMap::new();
+    for mut dice in outcomes.iter().cloned() {
+        dice.sort_unstable();
+        let total = match combiner {
+            Combiner::Sum => dice.iter().sum(),
+            Combiner::Product => dice.iter().product(),
+            Combiner::Difference if dice.len() > 1 => dice[dice.len() - 1] - dice[0],
+            Combiner::Difference | Combiner::Max => dice[dice.len() - 1],
+        };
+        let roll = if keep_dice { dice } else { vec![total] };
+        *roll_probs.entry(roll).or_insert(0.) += 1. / outcomes.len() as f64;
+    }
+    roll_probs
+}
+
+proptest! {
+    #[test]
+    fn removals_sum_to_roll(
+        mut tiles in prop::collection::vec(1..=12 as Uns, 0..=10),
+        removal_max in 0..=4 as Uns,
+    ) {
+        tiles.sort_unstable();
+        let rolls: Vec<Uns> = (1..=18).collect();
+        let trphm = get_tile_removal_possibilities(&tiles, &rolls, &removal_max);
+        for roll in rolls {
+            let mut removals = BTreeSet::new();
+            for removal in &trphm[&roll] {
+                prop_assert_eq!(removal.iter().sum::<Uns>(), roll);
+                prop_assert!(removal_max == 0 || removal.len() <= removal_max as usize);
+                let mut left = tiles.clone();
+                for tile in removal {
+                    let pos = left.iter().position(|x| x == tile);
+                    prop_assert!(pos.is_some(), "{:?} isn't part of {:?}", removal, tiles);
+                    left.remove(pos.unwrap());
+                }
+                let mut removal = removal.clone();
+                removal.sort_unstable();
+                removals.insert(removal);
+            }
+            prop_assert_eq!(removals, get_brute_force_removals(&tiles, roll, removal_max));
+        }
+    }
+
+    #[test]
+    fn roll_probabilities_match_enumeration(
+        die_vals in prop::collection::vec(1..=8 as Uns, 1..=6),
+        die_cnt in 1..=3 as Uns,
+        keep_dice in any::<bool>(),
+        combiner in prop_oneof![
+            Just(Combiner::Sum),
+            Just(Combiner::Product),
+            Just(Combiner::Difference),
+            Just(Combiner::Max),
+        ],
+    ) {
+        let roll_probs = get_roll_probs(&die_vals, die_cnt, keep_dice, combiner);
+        let total: f64 = roll_probs.values().sum();
+        prop_assert!((total - 1.).abs() < 1e-12);
+        let enumerated_probs = get_enumerated_roll_probs(&die_vals, die_cnt, keep_dice, combiner);
+        prop_assert_eq!(roll_probs.len(), enumerated_probs.len());
+        for (roll, prob) in &roll_probs {
+            prop_assert!((prob - enumerated_probs[roll]).abs() < 1e-12, "{:?}", roll);
+        }
+    }
+
+    #[test]
+    fn many_dice_probabilities_sum_to_one(sides in 2..=20 as Uns, die_cnt in 4..=8 as Uns) {
+        let die_vals: Vec<Uns> = (1..=sides).collect();
+        let roll_probs = get_roll_probs(&die_vals, die_cnt, false, Combiner::Sum);
+        prop_assert_eq!(roll_probs.len() as Uns, (sides - 1) * die_cnt + 1);
+        let total: f64 = roll_probs.values().sum();
+        prop_assert!((total - 1.).abs() < 1e-9);
+    }
+}
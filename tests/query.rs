@@ -0,0 +1,118 @@
+use shut_the_box_rust::query::{get_policy, query_roll, query_state, BestMove};
+use shut_the_box_rust::{
+    get_trunk, get_trunk_from, try_get_solvable_game_meta, Float, InitData, RemovalLimits,
+    RemovalRule,
+};
+
+/// Query results agree with solving when they differ by less than this
+const TOLERANCE: Float = 1e-9;
+
+#[test]
+fn query_classic_start() {
+    let mut trunk = get_trunk(
+        try_get_solvable_game_meta(&InitData::default()).expect("the rules are solvable"),
+    );
+    let state_query = query_state(&mut trunk, &(1..=9).collect()).unwrap();
+    assert!((state_query.win_chance - 0.097614).abs() < 5e-7);
+    assert!(!state_query.single_legal && !state_query.use_single);
+    let expected_score = state_query.expected_score.unwrap();
+    assert!((expected_score - 11.06).abs() < 0.01, "{}", expected_score);
+    let nine = state_query.choices.iter().find(|choice| choice.roll == [9]);
+    assert_eq!(nine.unwrap().best_move, BestMove::Flip(vec![9]));
+}
+
+#[test]
+fn query_unreached_state() {
+    // flipping exactly two tiles never leaves an even number of tiles open
+    let rules = |start_tiles| InitData {
+        start_tiles,
+        removal_limits: RemovalLimits {
+            exact_count: 2,
+            ..RemovalLimits::default()
+        },
+        ..InitData::default()
+    };
+    let mut trunk = get_trunk(
+        try_get_solvable_game_meta(&rules((1..=9).collect())).expect("the rules are solvable"),
+    );
+    let tiles = vec![8, 1, 2, 3, 4, 5, 6, 7];
+    assert!(!trunk.game_db.contains_key(&tiles));
+    let state_query = query_state(&mut trunk, &tiles).unwrap();
+
+    let mut fresh_trunk = get_trunk(
+        try_get_solvable_game_meta(&rules((1..=8).collect())).expect("the rules are solvable"),
+    );
+    let fresh_query = query_state(&mut fresh_trunk, &tiles).unwrap();
+    assert!((state_query.win_chance - fresh_query.win_chance).abs() < TOLERANCE);
+
+    assert!(query_state(&mut trunk, &vec![1, 10]).is_err());
+}
+
+#[test]
+fn mid_game_position() {
+    // going bust scores the whole board, so the board has to outlive the tiles still open
+    let rules = |open_tiles| InitData {
+        open_tiles,
+        stop_early: true,
+        ..InitData::default()
+    };
+    let init_data = rules(Some(vec![7, 4, 1]));
+    let open_tiles = init_data.get_open_tiles();
+    let mut trunk = get_trunk_from(
+        try_get_solvable_game_meta(&init_data).expect("the rules are solvable"),
+        &open_tiles,
+    );
+    // only 1 4 7 and the boards below it are solved
+    assert_eq!(trunk.game_db.len(), 8);
+
+    let mut full_trunk =
+        get_trunk(try_get_solvable_game_meta(&rules(None)).expect("the rules are solvable"));
+    assert!((trunk.game_db[&open_tiles] - full_trunk.game_db[&open_tiles]).abs() < TOLERANCE);
+    // playing to shut never stops, so every game that doesn't shut the box goes bust
+    let start_query = query_state(&mut full_trunk, &(1..=9).collect()).unwrap();
+    let expected_score = start_query.expected_score.unwrap();
+    assert!((expected_score - 40.61).abs() < 0.01, "{}", expected_score);
+
+    let roll_choice = query_roll(&mut trunk, &open_tiles, &[5, 3]).unwrap();
+    assert_eq!(roll_choice.roll, [8]);
+    assert_eq!(roll_choice.best_move, BestMove::Flip(vec![7, 1]));
+    // 7 is too high for a single die, and no die shows a 7
+    assert!(query_roll(&mut trunk, &open_tiles, &[3]).is_err());
+    assert!(query_roll(&mut trunk, &open_tiles, &[7, 1]).is_err());
+}
+
+#[test]
+fn policy_covers_every_state() {
+    let mut trunk = get_trunk(
+        try_get_solvable_game_meta(&InitData::default()).expect("the rules are solvable"),
+    );
+    let policy = get_policy(&trunk);
+    assert_eq!(policy.len(), 512);
+    let state_policy = policy
+        .iter()
+        .find(|state| state.tiles == [1, 4, 7])
+        .unwrap();
+    let state_query = query_state(&mut trunk, &vec![1, 4, 7]).unwrap();
+    assert_eq!(state_policy.choices.len(), state_query.choices.len());
+    for (policy_choice, query_choice) in state_policy.choices.iter().zip(&state_query.choices) {
+        assert_eq!(policy_choice.best_move, query_choice.best_move);
+    }
+}
+
+#[test]
+fn individual_dice_flip_their_tiles() {
+    // the limit on flipped tiles only applies to the dice total, so 6 6 can still flip the 6
+    let mut trunk = get_trunk(
+        try_get_solvable_game_meta(&InitData {
+            max_remove: 1,
+            removal_rule: RemovalRule::SumOrIndividual,
+            ..InitData::default()
+        })
+        .expect("the rules are solvable"),
+    );
+    let start_tiles = (1..=9).collect();
+    let roll_choice = query_roll(&mut trunk, &start_tiles, &[6, 6]).unwrap();
+    assert_eq!(roll_choice.best_move, BestMove::Flip(vec![6]));
+    let roll_choice = query_roll(&mut trunk, &vec![1, 2, 3], &[2, 3]).unwrap();
+    assert_eq!(roll_choice.best_move, BestMove::Flip(vec![3, 2]));
+}
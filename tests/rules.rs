@@ -0,0 +1,131 @@
+use shut_the_box_rust::rules::Rules;
+use shut_the_box_rust::{
+    get_trunk, try_get_solvable_game_meta, Float, InitData, RemovalLimits, Uns,
+};
+
+/// Solved win chances match the hand worked ones when they differ by less than this
+const TOLERANCE: Float = 1e-9;
+
+#[test]
+fn unknown_rules_are_rejected() {
+    let err = "d_max = 8\nsides = 6".parse::<Rules>().unwrap_err();
+    assert!(err.contains("line 2") && err.contains("sides"), "{}", err);
+    assert!("d_max 8".parse::<Rules>().is_err());
+}
+
+#[test]
+fn malformed_values_are_errors() {
+    let rules: Rules = "d-max = six\nt_direct = 1, two".parse().unwrap();
+    assert!(rules.get::<Uns>("d_max").unwrap_err().contains("six"));
+    assert!(rules
+        .get_list::<Uns>("t_direct")
+        .unwrap_err()
+        .contains("two"));
+    // a setting that can't be read is an error even when the command line gives the value
+    assert!(rules.get_or("d_max", Some(6 as Uns), 6).is_err());
+}
+
+#[test]
+fn command_line_values_win_over_file_settings() {
+    let rules: Rules =
+        "# a pub's rules\nd-max = 8  # bigger dice\nt_direct = 1, 2 3\nstop_early = true\n"
+            .parse()
+            .unwrap();
+    assert_eq!(rules.get_or("d_max", None, 6 as Uns), Ok(8));
+    assert_eq!(rules.get_or("d_max", Some(4 as Uns), 6), Ok(4));
+    assert_eq!(rules.get_flag("stop_early", false), Ok(true));
+    assert_eq!(
+        rules.get_list_or("t_direct", Vec::<Uns>::new()),
+        Ok(vec![1, 2, 3])
+    );
+    assert_eq!(rules.get_list_or("t_direct", vec![4 as Uns]), Ok(vec![4]));
+    // rules left out of both take their defaults
+    assert_eq!(rules.get_or("die_cnt", None, 2 as Uns), Ok(2));
+    assert_eq!(rules.get_list_or("d_direct", Vec::<Uns>::new()), Ok(vec![]));
+    assert_eq!(rules.get_flag("adjacent", false), Ok(false));
+    assert_eq!(rules.get::<Uns>("one_above"), Ok(None));
+
+    let rules: Rules = "adjacent = false".parse().unwrap();
+    assert_eq!(rules.get_flag("adjacent", true), Ok(true));
+}
+
+/// Returns the win chance of tiles 1 to 3 under the removal limits, rolling one die with the given faces
+fn get_limited_win_chance(die_max: Uns, removal_limits: RemovalLimits) -> Float {
+    let start_tiles = vec![1, 2, 3];
+    let trunk = get_trunk(
+        try_get_solvable_game_meta(&InitData {
+            die_vals: (1..=die_max).collect(),
+            die_cnt: 1,
+            start_tiles: start_tiles.clone(),
+            removal_limits,
+            ..InitData::default()
+        })
+        .expect("the rules are solvable"),
+    );
+    trunk.game_db[&start_tiles]
+}
+
+// each limit below is checked on a board small enough to work out by hand.
+// A single tile k is shut by rolling k, so it's worth 1 / the number of faces
+
+#[test]
+fn exact_count_win_chance() {
+    // with one four-sided die and single flips, two tiles need two rolls matching them: 2/16.
+    // From 1 2 3, a 1, 2, or 3 leaves two tiles and a 4 is stuck: 3/4 * 2/16
+    let win_chance = get_limited_win_chance(
+        4,
+        RemovalLimits {
+            exact_count: 1,
+            ..RemovalLimits::default()
+        },
+    );
+    assert!((win_chance - 3. / 32.).abs() < TOLERANCE, "{}", win_chance);
+}
+
+#[test]
+fn adjacent_win_chance() {
+    // with one four-sided die, 1 2 is worth (1/4 + 1/4 + 1) / 4 = 3/8, shutting both on a 3.
+    // 1 3 and 2 3 are worth 1/8, since 1 and 3 aren't neighbours and 4 can't shut them.
+    // From 1 2 3: a 1 or 2 leaves 1/8, a 3 shuts the 3 for 3/8, and a 4 is stuck: 5/32
+    let win_chance = get_limited_win_chance(
+        4,
+        RemovalLimits {
+            adjacent: true,
+            ..RemovalLimits::default()
+        },
+    );
+    assert!((win_chance - 5. / 32.).abs() < TOLERANCE, "{}", win_chance);
+}
+
+#[test]
+fn include_highest_win_chance() {
+    // with one four-sided die, 1 2 is worth (1/4 + 1) / 4 = 5/16, since a 1 can't leave the 2 open.
+    // From 1 2 3 only flips with the 3 are allowed: a 3 leaves 1 2, a 4 leaves 2: (5/16 + 1/4) / 4
+    let win_chance = get_limited_win_chance(
+        4,
+        RemovalLimits {
+            include_highest: true,
+            ..RemovalLimits::default()
+        },
+    );
+    assert!((win_chance - 9. / 64.).abs() < TOLERANCE, "{}", win_chance);
+}
+
+#[test]
+fn one_above_win_chance() {
+    // with one five-sided die and at most one tile above 1 per flip, 2 3 can't go down together on a 5.
+    // 1 2 and 1 3 are worth 7/25 and 2 3 is worth 2/25.
+    // From 1 2 3: a 1 leaves 2 3, a 2 leaves 1 3, a 3 leaves 1 2, a 4 leaves 2, and a 5 is stuck: 21/125
+    let win_chance = get_limited_win_chance(
+        5,
+        RemovalLimits {
+            one_above: Some(1),
+            ..RemovalLimits::default()
+        },
+    );
+    assert!(
+        (win_chance - 21. / 125.).abs() < TOLERANCE,
+        "{}",
+        win_chance
+    );
+}
@@ -0,0 +1,90 @@
+use shut_the_box_rust::query::{query_roll, BestMove};
+use shut_the_box_rust::{
+    get_game_meta, get_mulligan_game_meta, get_scoring_game_meta, get_trunk,
+    try_get_solvable_game_meta, Float, InitData, Scoring, Trunk,
+};
+use std::collections::HashMap;
+
+/// Solved values agree with hand values when they differ by less than this
+const TOLERANCE: Float = 1e-9;
+
+/// Returns the game of two dice numbered 1 and 2 on 1 2 3 10, solved for the lowest expected score.
+/// The totals 2, 3, and 4 come up a quarter, half, and quarter of the time, and the 10 is never flipped.
+fn get_small_scoring_trunk(scoring: Scoring) -> Trunk {
+    let init_data = InitData {
+        die_vals: vec![1, 2],
+        start_tiles: vec![1, 2, 3, 10],
+        ..InitData::default()
+    };
+    let game_meta = get_game_meta(&init_data).unwrap();
+    get_trunk(get_mulligan_game_meta(get_scoring_game_meta(
+        game_meta, scoring,
+    )))
+}
+
+#[test]
+fn digits_write_out_each_tile() {
+    let mut trunk = get_small_scoring_trunk(Scoring::Digits);
+    // no total flips anything from 1 10, which is written 110
+    assert!((trunk.game_db[&vec![1, 10]] + 110.).abs() < TOLERANCE);
+    // from 3 10 a 3 leaves 10, anything else leaves 310: (10 + 310) / 2
+    assert!((trunk.game_db[&vec![3, 10]] + 160.).abs() < TOLERANCE);
+    // from 1 2 10 a 2 leaves 110, a 3 leaves 10, and a 4 leaves 1210: 110 / 4 + 10 / 2 + 1210 / 4
+    assert!((trunk.game_db[&vec![1, 2, 10]] + 335.).abs() < TOLERANCE);
+    // from 1 3 10 a 2 leaves 1310, a 3 leaves 110, and a 4 leaves 10: 1310 / 4 + 110 / 2 + 10 / 4
+    assert!((trunk.game_db[&vec![1, 3, 10]] + 385.).abs() < TOLERANCE);
+    // from 2 10 only a 2 flips anything, leaving 10: 10 / 4 + 210 * 3 / 4
+    assert!((trunk.game_db[&vec![2, 10]] + 160.).abs() < TOLERANCE);
+    // from the start a 2 leaves 1 3 10, a 3 is best spent on 1 and 2 leaving 3 10, and a 4 leaves 2 10
+    let start_value = trunk.game_db[&vec![1, 2, 3, 10]];
+    assert!((start_value + (385. / 4. + 160. / 2. + 160. / 4.)).abs() < TOLERANCE);
+
+    // flipping the 3 would leave 1 2 10 and its 335, but by pip sum 3 10 expects 11.5 and 1 2 10 only 11
+    let start_tiles = vec![1, 2, 3, 10];
+    let roll_choice = query_roll(&mut trunk, &start_tiles, &[1, 2]).unwrap();
+    assert_eq!(roll_choice.best_move, BestMove::Flip(vec![2, 1]));
+    let mut pip_sum_trunk = get_small_scoring_trunk(Scoring::PipSum);
+    let roll_choice = query_roll(&mut pip_sum_trunk, &start_tiles, &[1, 2]).unwrap();
+    assert_eq!(roll_choice.best_move, BestMove::Flip(vec![3]));
+}
+
+#[test]
+fn tile_count_counts_each_tile() {
+    let trunk = get_small_scoring_trunk(Scoring::TileCount);
+    // 1 10 is stuck with two tiles, and 3 10 gets down to one tile on a 3: (1 + 2) / 2
+    assert!((trunk.game_db[&vec![1, 10]] + 2.).abs() < TOLERANCE);
+    assert!((trunk.game_db[&vec![3, 10]] + 1.5).abs() < TOLERANCE);
+    // from 1 2 10 a 2 leaves two tiles, a 3 one, and a 4 three: 2 / 4 + 1 / 2 + 3 / 4
+    assert!((trunk.game_db[&vec![1, 2, 10]] + 1.75).abs() < TOLERANCE);
+    // from the start a 2 leaves 1 3 10 and its 3 / 4 + 2 / 2 + 1 / 4 = 2,
+    // a 3 leaves 3 10 rather than 1 2 10, and a 4 leaves 2 10 and its 1 / 4 + 2 * 3 / 4 = 1.75
+    let start_value = trunk.game_db[&vec![1, 2, 3, 10]];
+    assert!((start_value + (2. / 4. + 1.5 / 2. + 1.75 / 4.)).abs() < TOLERANCE);
+}
+
+#[test]
+fn tile_points_change_scores_and_play() {
+    let init_data = InitData {
+        die_vals: vec![1, 2],
+        start_tiles: vec![1, 2, 3, 10],
+        tile_points: HashMap::from([(1, 20)]),
+        ..InitData::default()
+    };
+    let game_meta = try_get_solvable_game_meta(&init_data).expect("the rules are solvable");
+    let mut trunk = get_trunk(get_scoring_game_meta(game_meta, Scoring::PipSum));
+    // 1 10 is stuck, and its 1 scores 20
+    assert!((trunk.game_db[&vec![1, 10]] + 30.).abs() < TOLERANCE);
+    // from 1 2 10 a 2 leaves 1 10, a 3 leaves 10, and a 4 leaves every tile: 30 / 4 + 10 / 2 + 32 / 4
+    assert!((trunk.game_db[&vec![1, 2, 10]] + 20.5).abs() < TOLERANCE);
+    // 3 10 expects 11.5 as before, so a 3 is now best spent on the expensive 1 along with the 2
+    assert!((trunk.game_db[&vec![3, 10]] + 11.5).abs() < TOLERANCE);
+    let roll_choice = query_roll(&mut trunk, &vec![1, 2, 3, 10], &[1, 2]).unwrap();
+    assert_eq!(roll_choice.best_move, BestMove::Flip(vec![2, 1]));
+
+    // every report keeps a chance for each score, so boards worth too much are turned down
+    let expensive_rules = InitData {
+        tile_points: HashMap::from([(9, 1_000_000_000)]),
+        ..InitData::default()
+    };
+    assert!(try_get_solvable_game_meta(&expensive_rules).is_err());
+}
@@ -0,0 +1,100 @@
+use serde_json::{json, Value};
+use shut_the_box_rust::serve::ApiServer;
+use std::io::{Read, Write};
+use std::net::{SocketAddr, TcpStream};
+
+/// Starts a server on any free loopback port solving and keeping at most max_states game states,
+/// answering requests in the background
+fn start_server(max_states: u64) -> SocketAddr {
+    let server = ApiServer::bind(SocketAddr::from(([127, 0, 0, 1], 0)), max_states).unwrap();
+    let addr = server.get_addr();
+    std::thread::spawn(move || server.run());
+    addr
+}
+
+/// Posts a JSON body to a path and returns the status code and JSON body of the response
+fn post(addr: SocketAddr, path: &str, body: &Value) -> (u16, Value) {
+    let body = body.to_string();
+    let mut stream = TcpStream::connect(addr).unwrap();
+    write!(
+        stream,
+        "POST {} HTTP/1.1\r\nHost: {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        path,
+        addr,
+        body.len(),
+        body
+    )
+    .unwrap();
+    let mut response = String::new();
+    stream.read_to_string(&mut response).unwrap();
+    let (head, body) = response.split_once("\r\n\r\n").unwrap();
+    let status = head.split(' ').nth(1).unwrap().parse().unwrap();
+    (status, serde_json::from_str(body).unwrap())
+}
+
+#[test]
+fn solve_query_and_move() {
+    let addr = start_server(1 << 24);
+    let (status, solved) = post(addr, "/solve", &json!({}));
+    assert_eq!(status, 200);
+    assert!((solved["win_chance"].as_f64().unwrap() - 0.097614).abs() < 5e-7);
+    assert_eq!(solved["cached"], false);
+    // the same rules written out differently share the solved game
+    let classic = json!({ "rules": { "t_direct": [9, 8, 7, 6, 5, 4, 3, 2, 1], "d_max": 6 } });
+    assert_eq!(post(addr, "/solve", &classic).1["cached"], true);
+
+    let (status, state_query) = post(addr, "/query", &json!({ "tiles": [7, 4, 1] }));
+    assert_eq!(status, 200);
+    assert_eq!(state_query["tiles"], json!([1, 4, 7]));
+    assert_eq!(state_query["choices"].as_array().unwrap().len(), 11);
+
+    let (status, best_move) = post(
+        addr,
+        "/move",
+        &json!({ "tiles": [1, 4, 7], "roll": [5, 3] }),
+    );
+    assert_eq!(status, 200);
+    assert_eq!(best_move["move"], "flip");
+    assert_eq!(best_move["flip"], json!([7, 1]));
+}
+
+#[test]
+fn bad_requests() {
+    let addr = start_server(1 << 24);
+    let (status, body) = post(addr, "/solve", &json!({ "rules": { "mode": "sideways" } }));
+    assert_eq!(status, 400);
+    assert!(body["error"].as_str().unwrap().contains("sideways"));
+    // huge dice and boards are turned down before any faces are listed or totals added up
+    for rules in [
+        json!({ "d_max": 4_000_000_000u32 }),
+        json!({ "d_direct": [1, 3_000_000_000u32] }),
+        json!({ "t_direct": [4_000_000_000u32, 4_000_000_000u32] }),
+        json!({ "mulligans": 4_000_000_000u32 }),
+        json!({ "max_remove": 4_000_000_000u32 }),
+        json!({ "exact_count": 4_000_000_000u32 }),
+        json!({ "points": { "9": 1_000_000_000u32 } }),
+    ] {
+        assert_eq!(post(addr, "/solve", &json!({ "rules": rules })).0, 400);
+    }
+    assert_eq!(post(addr, "/move", &json!({ "tiles": [1, 4, 7] })).0, 400);
+    assert_eq!(post(addr, "/query", &json!({ "tiles": [1, 10] })).0, 400);
+    assert_eq!(post(addr, "/nowhere", &json!({})).0, 404);
+
+    assert!(ApiServer::bind(SocketAddr::from(([0, 0, 0, 0], 0)), 1 << 24).is_err());
+}
+
+#[test]
+fn cache_forgets_the_oldest_games() {
+    // the classic board has 512 game states and 1 to 8 has 256, too many to keep both
+    let addr = start_server(600);
+    let classic = json!({});
+    let smaller = json!({ "rules": { "t_max": 8 } });
+    assert_eq!(post(addr, "/solve", &classic).1["cached"], false);
+    assert_eq!(post(addr, "/solve", &classic).1["cached"], true);
+    assert_eq!(post(addr, "/solve", &smaller).1["cached"], false);
+    assert_eq!(post(addr, "/solve", &smaller).1["cached"], true);
+    assert_eq!(post(addr, "/solve", &classic).1["cached"], false);
+    // each mulligan solves the game again, so one mulligan on the classic board is over the limit
+    let mulligan = json!({ "rules": { "mulligans": 1 } });
+    assert_eq!(post(addr, "/solve", &mulligan).0, 400);
+}
@@ -0,0 +1,23 @@
+use shut_the_box_rust::simulate::simulate;
+use shut_the_box_rust::{get_trunk, try_get_solvable_game_meta, Float, InitData};
+
+#[test]
+fn simulated_games_match_solve() {
+    let mut trunk = get_trunk(
+        try_get_solvable_game_meta(&InitData::default()).expect("the rules are solvable"),
+    );
+    let start_tiles = (1..=9).collect();
+    let games = simulate(&mut trunk, &start_tiles, 20_000, 7).unwrap();
+    let won_cnt = games.iter().filter(|game| game.tiles.is_empty()).count();
+    let win_rate = won_cnt as Float / games.len() as Float;
+    // about 5 standard deviations of the win rate over 20,000 games
+    assert!((win_rate - 0.097614).abs() < 0.011, "{}", win_rate);
+    for game in &games {
+        assert_eq!(game.score, game.tiles.iter().sum::<u32>());
+    }
+
+    let replayed = simulate(&mut trunk, &start_tiles, 100, 7).unwrap();
+    for (game, replayed_game) in games.iter().zip(&replayed) {
+        assert_eq!(game.tiles, replayed_game.tiles);
+    }
+}
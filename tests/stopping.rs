@@ -0,0 +1,30 @@
+use shut_the_box_rust::stopping::get_readable_stopping_string;
+use shut_the_box_rust::utility::{get_readable_utility_string, Utility};
+use shut_the_box_rust::{try_get_solvable_game_meta, Float, InitData, Scoring};
+
+/// Returns the number at the end of the first line of a report starting with the given text
+fn get_reported_value(report: &str, start: &str) -> Float {
+    let line = report.lines().find(|line| line.starts_with(start)).unwrap();
+    line.split_whitespace().last().unwrap().parse().unwrap()
+}
+
+#[test]
+fn score_distribution_counts_going_bust() {
+    let game_meta = try_get_solvable_game_meta(&InitData {
+        stop_early: true,
+        ..InitData::default()
+    })
+    .expect("the rules are solvable");
+    // playing for the lowest expected score is the same play whichever report solves it
+    let utility_report = get_readable_utility_string(&game_meta, &Utility::LowScore, 0).unwrap();
+    let stopping_report = get_readable_stopping_string(&game_meta, Scoring::PipSum);
+    let distribution_score = get_reported_value(&utility_report, "Playing for the goal");
+    let stopping_score =
+        get_reported_value(&stopping_report, "Expected score stopping at the best time");
+    assert!(
+        (distribution_score - stopping_score).abs() < 1e-3,
+        "{} {}",
+        distribution_score,
+        stopping_score
+    );
+}
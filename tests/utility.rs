@@ -0,0 +1,18 @@
+use shut_the_box_rust::utility::{get_readable_utility_string, Utility};
+use shut_the_box_rust::{try_get_solvable_game_meta, InitData};
+use std::collections::HashMap;
+
+#[test]
+fn wide_score_ranges_are_turned_down() {
+    // a 4 worth 500 leaves scores up to 506, four means a point for the variance goal
+    let game_meta = try_get_solvable_game_meta(&InitData {
+        start_tiles: (1..=4).collect(),
+        tile_points: HashMap::from([(4, 500)]),
+        ..InitData::default()
+    })
+    .expect("the rules are solvable");
+    let err = get_readable_utility_string(&game_meta, &Utility::MeanVariance(0.5), 0).unwrap_err();
+    assert!(err.contains("506"), "{}", err);
+    // one threshold a point is few enough
+    assert!(get_readable_utility_string(&game_meta, &Utility::Cvar(0.1), 0).is_ok());
+}